@@ -2,23 +2,57 @@ mod repo;
 mod storage;
 mod watcher;
 
-use repo::diff;
-use repo::jj::JjRepo;
-use repo::log::{Revision, RevsetResult};
+use jj_lib::copies::CopyRecords;
+use jj_lib::merged_tree::MergedTree;
+use repo::diff::{self, DiffOptions, DiffSide, DiffStats, FileDiff, InlineGranularity, ParentDiff};
+use repo::divergence::DivergentCopy;
+use repo::evolog::EvologEntry;
+use repo::jj::{Direction, JjRepo, MovementResult};
+use repo::log::{LogFilter, LogPage, Revision, RevsetResult};
+use repo::revset::{RevsetCompletions, TrunkSettings};
+use repo::status::ChangedFile;
 use repo::status::WorkingCopyStatus;
-use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use storage::{AppLayout, Project, Storage, get_storage};
-use tauri::{AppHandle, Emitter, Manager};
+use storage::{AppLayout, Project, RevsetPreset, Storage, get_storage};
 use tauri::menu::{MenuBuilder, MenuItem, SubmenuBuilder};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_dialog::DialogExt;
 use watcher::{WatcherManager, get_watcher_manager};
 
-#[derive(Serialize)]
-pub struct ChangedFile {
-    pub path: String,
-    pub status: String,
+/// Trunk settings stored for the project at `repo_path`, if it is a known project
+async fn project_trunk(app: &AppHandle, repo_path: &str) -> TrunkSettings {
+    let storage = get_storage(app);
+    let project = storage.find_project_by_path(repo_path).await.ok().flatten();
+    project
+        .map(|project| TrunkSettings {
+            bookmark: project
+                .trunk_bookmark
+                .filter(|bookmark| !bookmark.is_empty()),
+            remote: project.trunk_remote.filter(|remote| !remote.is_empty()),
+        })
+        .unwrap_or_default()
+}
+
+/// Diff options stored for the project at `repo_path`, or the defaults
+async fn project_diff_options(app: &AppHandle, repo_path: &str) -> DiffOptions {
+    let storage = get_storage(app);
+    let project = storage.find_project_by_path(repo_path).await.ok().flatten();
+    project
+        .and_then(|project| project.diff_options)
+        .unwrap_or_default()
+}
+
+/// Custom revset presets stored for the project at `repo_path`
+async fn project_revset_presets(app: &AppHandle, repo_path: &str) -> Vec<RevsetPreset> {
+    let storage = get_storage(app);
+    let Ok(Some(project)) = storage.find_project_by_path(repo_path).await else {
+        return Vec::new();
+    };
+    storage
+        .get_revset_presets(&project.id)
+        .await
+        .unwrap_or_default()
 }
 
 #[tauri::command]
@@ -29,29 +63,71 @@ fn find_repository(start_path: String) -> Option<String> {
 
 #[tauri::command]
 async fn get_revisions(
+    app: tauri::AppHandle,
     repo_path: String,
     limit: usize,
     revset: Option<String>,
     preset: Option<String>,
+    paths: Option<Vec<String>>,
 ) -> Result<Vec<Revision>, String> {
     let path = Path::new(&repo_path);
-    repo::log::fetch_log(path, limit, revset.as_deref(), preset.as_deref())
+    let trunk = project_trunk(&app, &repo_path).await;
+    let custom_presets = project_revset_presets(&app, &repo_path).await;
+    let filter = LogFilter {
+        revset: revset.as_deref(),
+        preset: preset.as_deref(),
+        custom_presets: &custom_presets,
+        paths: paths.as_deref().unwrap_or_default(),
+    };
+    let options = project_diff_options(&app, &repo_path).await;
+    repo::log::fetch_log(path, limit, &filter, &trunk, &options)
+        .map_err(|e| format!("Failed to fetch log: {}", e))
+}
+
+#[tauri::command]
+async fn get_revisions_page(
+    app: tauri::AppHandle,
+    repo_path: String,
+    limit: usize,
+    cursor: Option<String>,
+    revset: Option<String>,
+    preset: Option<String>,
+    paths: Option<Vec<String>>,
+) -> Result<LogPage, String> {
+    let path = Path::new(&repo_path);
+    let trunk = project_trunk(&app, &repo_path).await;
+    let custom_presets = project_revset_presets(&app, &repo_path).await;
+    let filter = LogFilter {
+        revset: revset.as_deref(),
+        preset: preset.as_deref(),
+        custom_presets: &custom_presets,
+        paths: paths.as_deref().unwrap_or_default(),
+    };
+    let options = project_diff_options(&app, &repo_path).await;
+    repo::log::fetch_log_page(path, limit, cursor.as_deref(), &filter, &trunk, &options)
         .map_err(|e| format!("Failed to fetch log: {}", e))
 }
 
 #[tauri::command]
-async fn get_status(repo_path: String) -> Result<WorkingCopyStatus, String> {
+async fn get_status(app: tauri::AppHandle, repo_path: String) -> Result<WorkingCopyStatus, String> {
     let path = Path::new(&repo_path);
-    repo::status::fetch_status(path).map_err(|e| format!("Failed to fetch status: {}", e))
+    let options = project_diff_options(&app, &repo_path).await;
+    repo::status::fetch_status(path, &options).map_err(|e| format!("Failed to fetch status: {}", e))
 }
 
 #[tauri::command]
 async fn get_file_diff(
+    app: tauri::AppHandle,
     repo_path: String,
     change_id: String,
     file_path: String,
+    options: Option<DiffOptions>,
 ) -> Result<String, String> {
     let path = Path::new(&repo_path);
+    let options = match options {
+        Some(options) => options,
+        None => project_diff_options(&app, &repo_path).await,
+    };
     let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
 
     let commit = jj_repo
@@ -66,15 +142,19 @@ async fn get_file_diff(
         .get_file_content(&commit, &file_path)
         .unwrap_or_default();
 
-    diff::compute_file_diff(&old_content, &new_content, &file_path)
+    diff::compute_file_diff(&old_content, &new_content, &file_path, &options)
         .map_err(|e| format!("Failed to compute diff: {}", e))
 }
 
+/// Raw content of a file before (`old`, in the merged parents) or after (`new`) a
+/// revision, returned as bytes so the frontend can render images and other binaries.
 #[tauri::command]
-async fn get_revision_diff(repo_path: String, change_id: String) -> Result<String, String> {
-    use jj_lib::backend::TreeValue;
-    use jj_lib::matchers::EverythingMatcher;
-
+async fn get_file_bytes(
+    repo_path: String,
+    change_id: String,
+    file_path: String,
+    side: DiffSide,
+) -> Result<tauri::ipc::Response, String> {
     let path = Path::new(&repo_path);
     let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
 
@@ -82,131 +162,325 @@ async fn get_revision_diff(repo_path: String, change_id: String) -> Result<Strin
         .get_commit(&change_id)
         .map_err(|e| format!("Failed to get commit: {}", e))?;
 
-    let parent_tree = {
-        let parents = commit.parents();
-        let parent = parents
-            .into_iter()
-            .next()
-            .ok_or_else(|| "Commit has no parent".to_string())?;
-        parent
-            .map_err(|e| format!("Failed to get parent: {}", e))?
-            .tree()
-            .map_err(|e| format!("Failed to get parent tree: {}", e))?
+    let content = match side {
+        DiffSide::Old => jj_repo.get_parent_file_content(&commit, &file_path),
+        DiffSide::New => jj_repo.get_file_content(&commit, &file_path),
+    }
+    .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    Ok(tauri::ipc::Response::new(content))
+}
+
+/// Unified diff of a revision. Plain unified diffs can't express moves, so moved
+/// blocks are only reported by `get_revision_hunks`.
+#[tauri::command]
+async fn get_revision_diff(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    fileset: Option<String>,
+    options: Option<DiffOptions>,
+) -> Result<String, String> {
+    let path = Path::new(&repo_path);
+    let options = match options {
+        Some(options) => options,
+        None => project_diff_options(&app, &repo_path).await,
     };
+    let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
+
+    let commit = jj_repo
+        .get_commit(&change_id)
+        .map_err(|e| format!("Failed to get commit: {}", e))?;
 
+    let parent_tree = jj_repo
+        .get_parent_tree(&commit)
+        .map_err(|e| format!("Failed to get parent tree: {}", e))?;
     let commit_tree = commit
         .tree()
         .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+    let copy_records = diff::parent_copy_records(&commit)
+        .map_err(|e| format!("Failed to get copy records: {}", e))?;
+
+    let matcher = jj_repo
+        .fileset_matcher(fileset.as_deref())
+        .map_err(|e| format!("Failed to parse fileset: {}", e))?;
+
+    diff::compute_tree_diff(
+        &parent_tree,
+        &commit_tree,
+        matcher.as_ref(),
+        &copy_records,
+        &options,
+    )
+    .map_err(|e| format!("Failed to compute diff: {}", e))
+}
 
-    let matcher = EverythingMatcher;
-    let mut diff_iter = parent_tree.diff_stream(&commit_tree, &matcher);
+/// Structured version of `get_revision_diff`: files, hunks and numbered lines.
+#[tauri::command]
+async fn get_revision_hunks(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    fileset: Option<String>,
+    granularity: Option<InlineGranularity>,
+    options: Option<DiffOptions>,
+) -> Result<Vec<FileDiff>, String> {
+    let path = Path::new(&repo_path);
+    let options = match options {
+        Some(options) => options,
+        None => project_diff_options(&app, &repo_path).await,
+    };
+    let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
 
-    let mut unified_diffs = Vec::new();
+    let commit = jj_repo
+        .get_commit(&change_id)
+        .map_err(|e| format!("Failed to get commit: {}", e))?;
 
-    pollster::block_on(async {
-        use futures::StreamExt;
-        while let Some(entry) = diff_iter.next().await {
-            let path = entry.path;
-            let path_str = path.as_internal_file_string();
+    let parent_tree = jj_repo
+        .get_parent_tree(&commit)
+        .map_err(|e| format!("Failed to get parent tree: {}", e))?;
+    let commit_tree = commit
+        .tree()
+        .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+    let copy_records = diff::parent_copy_records(&commit)
+        .map_err(|e| format!("Failed to get copy records: {}", e))?;
+
+    let matcher = jj_repo
+        .fileset_matcher(fileset.as_deref())
+        .map_err(|e| format!("Failed to parse fileset: {}", e))?;
+
+    diff::compute_structured_tree_diff(
+        &parent_tree,
+        &commit_tree,
+        matcher.as_ref(),
+        &copy_records,
+        &options,
+        granularity.unwrap_or_default(),
+    )
+    .map_err(|e| format!("Failed to compute diff: {}", e))
+}
 
-            let diff_values = entry
-                .values
-                .map_err(|e| format!("Failed to get diff values: {}", e))?;
-            let before = diff_values.before.removes().next().and_then(|v| v.as_ref());
-            let after = diff_values.after.adds().next().and_then(|v| v.as_ref());
+/// Diff of a commit against each of its parents separately. For merges this shows
+/// what each side brought in, unlike `get_revision_diff`.
+#[tauri::command]
+async fn get_revision_parent_diffs(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    fileset: Option<String>,
+) -> Result<Vec<ParentDiff>, String> {
+    let path = Path::new(&repo_path);
+    let options = project_diff_options(&app, &repo_path).await;
+    let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
 
-            match (before, after) {
-                (Some(TreeValue::File { .. }), Some(TreeValue::File { .. }))
-                | (None, Some(TreeValue::File { .. }))
-                | (Some(TreeValue::File { .. }), None) => {
-                    let old_content = jj_repo
-                        .get_parent_file_content(&commit, path_str)
-                        .unwrap_or_default();
+    let commit = jj_repo
+        .get_commit(&change_id)
+        .map_err(|e| format!("Failed to get commit: {}", e))?;
 
-                    let new_content = jj_repo
-                        .get_file_content(&commit, path_str)
-                        .unwrap_or_default();
+    let matcher = jj_repo
+        .fileset_matcher(fileset.as_deref())
+        .map_err(|e| format!("Failed to parse fileset: {}", e))?;
 
-                    let file_diff = diff::compute_file_diff(&old_content, &new_content, path_str)
-                        .map_err(|e| format!("Failed to compute diff: {}", e))?;
+    diff::compute_parent_diffs(&commit, matcher.as_ref(), &options)
+        .map_err(|e| format!("Failed to compute diff: {}", e))
+}
 
-                    if !file_diff.is_empty() {
-                        unified_diffs.push(file_diff);
-                    }
-                }
-                _ => continue,
-            };
-        }
-        Ok::<(), String>(())
-    })?;
+/// Diff two versions of the same change (e.g. before and after a rebase),
+/// showing only what changed in the change itself.
+#[tauri::command]
+async fn get_interdiff(
+    app: tauri::AppHandle,
+    repo_path: String,
+    from_commit_id: String,
+    to_commit_id: String,
+) -> Result<String, String> {
+    use jj_lib::matchers::EverythingMatcher;
 
-    Ok(unified_diffs.join("\n"))
+    let path = Path::new(&repo_path);
+    let options = project_diff_options(&app, &repo_path).await;
+    let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
+    let repo = jj_repo
+        .repo_loader()
+        .load_at_head()
+        .map_err(|e| format!("Failed to load repo: {}", e))?;
+
+    let from_commit = jj_repo
+        .get_commit_by_id(&from_commit_id)
+        .map_err(|e| format!("Failed to get commit: {}", e))?;
+    let to_commit = jj_repo
+        .get_commit_by_id(&to_commit_id)
+        .map_err(|e| format!("Failed to get commit: {}", e))?;
+
+    diff::compute_interdiff(
+        repo.as_ref(),
+        &from_commit,
+        &to_commit,
+        &EverythingMatcher,
+        &options,
+    )
+    .map_err(|e| format!("Failed to compute interdiff: {}", e))
 }
 
 #[tauri::command]
 async fn get_revision_changes(
+    app: tauri::AppHandle,
     repo_path: String,
     change_id: String,
+    fileset: Option<String>,
 ) -> Result<Vec<ChangedFile>, String> {
-    use jj_lib::backend::TreeValue;
-    use jj_lib::matchers::EverythingMatcher;
-
     let path = Path::new(&repo_path);
+    let options = project_diff_options(&app, &repo_path).await;
     let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
 
     let commit = jj_repo
         .get_commit(&change_id)
         .map_err(|e| format!("Failed to get commit: {}", e))?;
 
-    let parent_tree = {
-        let parents = commit.parents();
-        let parent = parents
-            .into_iter()
-            .next()
-            .ok_or_else(|| "Commit has no parent".to_string())?;
-        parent
-            .map_err(|e| format!("Failed to get parent: {}", e))?
-            .tree()
-            .map_err(|e| format!("Failed to get parent tree: {}", e))?
-    };
-
+    let parent_tree = jj_repo
+        .get_parent_tree(&commit)
+        .map_err(|e| format!("Failed to get parent tree: {}", e))?;
     let commit_tree = commit
         .tree()
         .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+    let copy_records = diff::parent_copy_records(&commit)
+        .map_err(|e| format!("Failed to get copy records: {}", e))?;
+
+    let matcher = jj_repo
+        .fileset_matcher(fileset.as_deref())
+        .map_err(|e| format!("Failed to parse fileset: {}", e))?;
+
+    diff::compute_changed_files(
+        &parent_tree,
+        &commit_tree,
+        matcher.as_ref(),
+        &copy_records,
+        &options,
+    )
+    .map_err(|e| format!("Failed to get changes: {}", e))
+}
 
-    let matcher = EverythingMatcher;
-    let mut diff_iter = parent_tree.diff_stream(&commit_tree, &matcher);
+/// Totals for `get_revision_changes`, like the summary line of `jj diff --stat`
+#[tauri::command]
+async fn get_revision_stats(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    fileset: Option<String>,
+) -> Result<DiffStats, String> {
+    let path = Path::new(&repo_path);
+    let options = project_diff_options(&app, &repo_path).await;
+    let jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
 
-    let mut files = Vec::new();
+    let commit = jj_repo
+        .get_commit(&change_id)
+        .map_err(|e| format!("Failed to get commit: {}", e))?;
 
-    pollster::block_on(async {
-        use futures::StreamExt;
-        while let Some(entry) = diff_iter.next().await {
-            let path = entry.path;
-            let path_str = path.as_internal_file_string();
+    let parent_tree = jj_repo
+        .get_parent_tree(&commit)
+        .map_err(|e| format!("Failed to get parent tree: {}", e))?;
+    let commit_tree = commit
+        .tree()
+        .map_err(|e| format!("Failed to get commit tree: {}", e))?;
+    let copy_records = diff::parent_copy_records(&commit)
+        .map_err(|e| format!("Failed to get copy records: {}", e))?;
+
+    let matcher = jj_repo
+        .fileset_matcher(fileset.as_deref())
+        .map_err(|e| format!("Failed to parse fileset: {}", e))?;
+
+    let files = diff::compute_changed_files(
+        &parent_tree,
+        &commit_tree,
+        matcher.as_ref(),
+        &copy_records,
+        &options,
+    )
+    .map_err(|e| format!("Failed to compute stats: {}", e))?;
+    Ok(diff::changed_files_stats(&files))
+}
 
-            let diff_values = entry
-                .values
-                .map_err(|e| format!("Failed to get diff values: {}", e))?;
-            let before = diff_values.before.removes().next().and_then(|v| v.as_ref());
-            let after = diff_values.after.adds().next().and_then(|v| v.as_ref());
+/// Diff between two arbitrary revisions, like `jj diff --from <from> --to <to>`.
+/// `from` and `to` are revsets that must each resolve to a single revision.
+#[tauri::command]
+async fn get_range_diff(
+    app: tauri::AppHandle,
+    repo_path: String,
+    from: String,
+    to: String,
+    fileset: Option<String>,
+) -> Result<String, String> {
+    let path = Path::new(&repo_path);
+    let trunk = project_trunk(&app, &repo_path).await;
+    let jj_repo =
+        JjRepo::open_with_trunk(path, &trunk).map_err(|e| format!("Failed to open repo: {}", e))?;
+
+    let options = project_diff_options(&app, &repo_path).await;
+    let (from_tree, to_tree, copy_records) = range_trees(&jj_repo, &from, &to)?;
+    let matcher = jj_repo
+        .fileset_matcher(fileset.as_deref())
+        .map_err(|e| format!("Failed to parse fileset: {}", e))?;
+
+    diff::compute_tree_diff(
+        &from_tree,
+        &to_tree,
+        matcher.as_ref(),
+        &copy_records,
+        &options,
+    )
+    .map_err(|e| format!("Failed to compute diff: {}", e))
+}
 
-            let status = match (before, after) {
-                (Some(TreeValue::File { .. }), Some(TreeValue::File { .. })) => "modified",
-                (None, Some(_)) => "added",
-                (Some(_), None) => "deleted",
-                _ => continue,
-            };
+/// Files changed between two arbitrary revisions (see `get_range_diff`)
+#[tauri::command]
+async fn get_range_changes(
+    app: tauri::AppHandle,
+    repo_path: String,
+    from: String,
+    to: String,
+    fileset: Option<String>,
+) -> Result<Vec<ChangedFile>, String> {
+    let path = Path::new(&repo_path);
+    let trunk = project_trunk(&app, &repo_path).await;
+    let jj_repo =
+        JjRepo::open_with_trunk(path, &trunk).map_err(|e| format!("Failed to open repo: {}", e))?;
+
+    let options = project_diff_options(&app, &repo_path).await;
+    let (from_tree, to_tree, copy_records) = range_trees(&jj_repo, &from, &to)?;
+    let matcher = jj_repo
+        .fileset_matcher(fileset.as_deref())
+        .map_err(|e| format!("Failed to parse fileset: {}", e))?;
+
+    diff::compute_changed_files(
+        &from_tree,
+        &to_tree,
+        matcher.as_ref(),
+        &copy_records,
+        &options,
+    )
+    .map_err(|e| format!("Failed to get changes: {}", e))
+}
 
-            files.push(ChangedFile {
-                path: path_str.to_string(),
-                status: status.to_string(),
-            });
-        }
-        Ok::<(), String>(())
-    })?;
+/// Trees of the `from` and `to` revisions, plus the copies recorded between them
+fn range_trees(
+    jj_repo: &JjRepo,
+    from: &str,
+    to: &str,
+) -> Result<(MergedTree, MergedTree, CopyRecords), String> {
+    let resolve = |revset: &str| {
+        jj_repo
+            .resolve_single_revision(revset)
+            .map_err(|e| format!("Failed to resolve revision: {}", e))
+    };
+    let (from_commit, to_commit) = (resolve(from)?, resolve(to)?);
 
-    Ok(files)
+    let tree = |commit: &jj_lib::commit::Commit| {
+        commit
+            .tree()
+            .map_err(|e| format!("Failed to get commit tree: {}", e))
+    };
+    let copy_records = diff::copy_records(std::slice::from_ref(&from_commit), &to_commit)
+        .map_err(|e| format!("Failed to get copy records: {}", e))?;
+    Ok((tree(&from_commit)?, tree(&to_commit)?, copy_records))
 }
 
 #[tauri::command]
@@ -248,6 +522,36 @@ async fn remove_project(app: tauri::AppHandle, project_id: String) -> Result<(),
         .map_err(|e| format!("Failed to remove project: {}", e))
 }
 
+#[tauri::command]
+async fn get_revset_presets(
+    app: tauri::AppHandle,
+    project_id: String,
+) -> Result<Vec<RevsetPreset>, String> {
+    let storage = get_storage(&app);
+    storage
+        .get_revset_presets(&project_id)
+        .await
+        .map_err(|e| format!("Failed to get revset presets: {}", e))
+}
+
+#[tauri::command]
+async fn upsert_revset_preset(app: tauri::AppHandle, preset: RevsetPreset) -> Result<(), String> {
+    let storage = get_storage(&app);
+    storage
+        .upsert_revset_preset(&preset)
+        .await
+        .map_err(|e| format!("Failed to upsert revset preset: {}", e))
+}
+
+#[tauri::command]
+async fn remove_revset_preset(app: tauri::AppHandle, preset_id: String) -> Result<(), String> {
+    let storage = get_storage(&app);
+    storage
+        .delete_revset_preset(&preset_id)
+        .await
+        .map_err(|e| format!("Failed to remove revset preset: {}", e))
+}
+
 #[tauri::command]
 async fn get_layout(app: tauri::AppHandle) -> AppLayout {
     let storage = get_storage(&app);
@@ -299,23 +603,113 @@ async fn jj_new(
 }
 
 #[tauri::command]
-async fn jj_edit(repo_path: String, change_id: String) -> Result<(), String> {
+async fn jj_edit(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    ignore_immutable: bool,
+) -> Result<(), String> {
     let path = Path::new(&repo_path);
-    let mut jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
+    let trunk = project_trunk(&app, &repo_path).await;
+    let mut jj_repo =
+        JjRepo::open_with_trunk(path, &trunk).map_err(|e| format!("Failed to open repo: {}", e))?;
     jj_repo
-        .edit_revision(change_id)
+        .edit_revision(change_id, ignore_immutable)
         .map_err(|e| format!("Failed to edit revision: {}", e))
 }
 
 #[tauri::command]
-async fn jj_abandon(repo_path: String, change_id: String) -> Result<(), String> {
+async fn jj_abandon(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    ignore_immutable: bool,
+) -> Result<(), String> {
     let path = Path::new(&repo_path);
-    let mut jj_repo = JjRepo::open(path).map_err(|e| format!("Failed to open repo: {}", e))?;
+    let trunk = project_trunk(&app, &repo_path).await;
+    let mut jj_repo =
+        JjRepo::open_with_trunk(path, &trunk).map_err(|e| format!("Failed to open repo: {}", e))?;
     jj_repo
-        .abandon_revision(&change_id)
+        .abandon_revision(&change_id, ignore_immutable)
         .map_err(|e| format!("Failed to abandon revision: {}", e))
 }
 
+/// List the copies of a divergent change and how each differs from the first.
+#[tauri::command]
+async fn get_divergent_copies(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+) -> Result<Vec<DivergentCopy>, String> {
+    let path = Path::new(&repo_path);
+    let options = project_diff_options(&app, &repo_path).await;
+    repo::divergence::fetch_divergent_copies(path, &change_id, &options)
+        .map_err(|e| format!("Failed to fetch divergent copies: {}", e))
+}
+
+/// Resolve a divergent change by abandoning the given copies and/or giving
+/// one copy a fresh change ID.
+#[tauri::command]
+async fn jj_resolve_divergence(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    abandon_commit_ids: Vec<String>,
+    renew_commit_id: Option<String>,
+    ignore_immutable: bool,
+) -> Result<(), String> {
+    let path = Path::new(&repo_path);
+    let trunk = project_trunk(&app, &repo_path).await;
+    let mut jj_repo =
+        JjRepo::open_with_trunk(path, &trunk).map_err(|e| format!("Failed to open repo: {}", e))?;
+    jj_repo
+        .resolve_divergence(
+            &change_id,
+            abandon_commit_ids,
+            renew_commit_id,
+            ignore_immutable,
+        )
+        .map_err(|e| format!("Failed to resolve divergence: {}", e))
+}
+
+/// Move `@` to a child revision (`jj next`), or edit it directly with `edit`.
+/// Returns the candidates instead of moving when the target is ambiguous.
+#[tauri::command]
+async fn jj_next(
+    app: tauri::AppHandle,
+    repo_path: String,
+    offset: u64,
+    edit: bool,
+    ignore_immutable: bool,
+) -> Result<MovementResult, String> {
+    let path = Path::new(&repo_path);
+    let trunk = project_trunk(&app, &repo_path).await;
+    let mut jj_repo =
+        JjRepo::open_with_trunk(path, &trunk).map_err(|e| format!("Failed to open repo: {}", e))?;
+    jj_repo
+        .move_relative(Direction::Next, offset, edit, ignore_immutable)
+        .map_err(|e| format!("Failed to move to next revision: {}", e))
+}
+
+/// Move `@` to a parent revision (`jj prev`), or edit it directly with `edit`.
+/// Returns the candidates instead of moving when the target is ambiguous.
+#[tauri::command]
+async fn jj_prev(
+    app: tauri::AppHandle,
+    repo_path: String,
+    offset: u64,
+    edit: bool,
+    ignore_immutable: bool,
+) -> Result<MovementResult, String> {
+    let path = Path::new(&repo_path);
+    let trunk = project_trunk(&app, &repo_path).await;
+    let mut jj_repo =
+        JjRepo::open_with_trunk(path, &trunk).map_err(|e| format!("Failed to open repo: {}", e))?;
+    jj_repo
+        .move_relative(Direction::Prev, offset, edit, ignore_immutable)
+        .map_err(|e| format!("Failed to move to previous revision: {}", e))
+}
+
 /// Get recency data for commits by walking the operation log.
 /// Returns a map of commit_id (hex) -> timestamp_millis (when it was last the working copy).
 #[tauri::command]
@@ -330,57 +724,100 @@ async fn get_commit_recency(
         .map_err(|e| format!("Failed to get commit recency: {}", e))
 }
 
+/// List every recorded version of a change, newest first, with the operation
+/// that produced it and the diff from the previous version.
+#[tauri::command]
+async fn get_evolog(
+    app: tauri::AppHandle,
+    repo_path: String,
+    change_id: String,
+    limit: usize,
+) -> Result<Vec<EvologEntry>, String> {
+    let path = Path::new(&repo_path);
+    let options = project_diff_options(&app, &repo_path).await;
+    repo::evolog::fetch_evolog(path, &change_id, limit, &options)
+        .map_err(|e| format!("Failed to fetch evolog: {}", e))
+}
+
 /// Resolve a revset expression and return matching change IDs.
 /// Uses jj-lib's full revset parser.
 #[tauri::command]
-async fn resolve_revset(repo_path: String, revset: String) -> Result<RevsetResult, String> {
+async fn resolve_revset(
+    app: tauri::AppHandle,
+    repo_path: String,
+    revset: String,
+) -> Result<RevsetResult, String> {
     let path = Path::new(&repo_path);
-    repo::log::resolve_revset(path, &revset).map_err(|e| format!("Failed to resolve revset: {}", e))
+    let trunk = project_trunk(&app, &repo_path).await;
+    repo::log::resolve_revset(path, &revset, &trunk)
+        .map_err(|e| format!("Failed to resolve revset: {}", e))
+}
+
+#[tauri::command]
+async fn complete_revset(
+    app: tauri::AppHandle,
+    repo_path: String,
+    input: String,
+) -> Result<RevsetCompletions, String> {
+    let path = Path::new(&repo_path);
+    let trunk = project_trunk(&app, &repo_path).await;
+    repo::log::complete_revset(path, &input, &trunk)
+        .map_err(|e| format!("Failed to complete revset: {}", e))
 }
 
 /// Handle "Open Project" menu action: show folder picker, find jj repo, save project, emit event
 fn handle_open_project(app_handle: &AppHandle) {
     let handle = app_handle.clone();
-    
+
     app_handle.dialog().file().pick_folder(move |folder_path| {
         let Some(folder) = folder_path else { return };
         let path_str = folder.to_string();
-        
+
         // Find jj repo root
         let Some(repo_path) = repo::find_jj_repo(&PathBuf::from(&path_str)) else {
             // TODO: Could show an error dialog here
             return;
         };
         let repo_path_str = repo_path.to_string_lossy().to_string();
-        
+
         // Save project and emit event for frontend navigation
         let handle_clone = handle.clone();
         tauri::async_runtime::spawn(async move {
             let storage = get_storage(&handle_clone);
-            
+
             // Check if project already exists
-            let existing = storage.find_project_by_path(&repo_path_str).await.ok().flatten();
-            let project_id = existing.as_ref().map(|p| p.id.clone())
+            let existing = storage
+                .find_project_by_path(&repo_path_str)
+                .await
+                .ok()
+                .flatten();
+            let project_id = existing
+                .as_ref()
+                .map(|p| p.id.clone())
                 .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-            
-            let name = repo_path.file_name()
+
+            let name = repo_path
+                .file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("Unknown")
                 .to_string();
-            
+
             let project = Project {
                 id: project_id.clone(),
                 path: repo_path_str,
                 name,
                 last_opened_at: chrono::Utc::now().timestamp_millis(),
                 revset_preset: None,
+                trunk_bookmark: existing.as_ref().and_then(|p| p.trunk_bookmark.clone()),
+                trunk_remote: existing.as_ref().and_then(|p| p.trunk_remote.clone()),
+                diff_options: existing.as_ref().and_then(|p| p.diff_options.clone()),
             };
-            
+
             if let Err(e) = storage.upsert_project(&project).await {
                 eprintln!("Failed to save project: {}", e);
                 return;
             }
-            
+
             // Emit event for frontend to navigate
             let _ = handle_clone.emit("open-project", project_id);
         });
@@ -388,8 +825,14 @@ fn handle_open_project(app_handle: &AppHandle) {
 }
 
 fn build_app_menu(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    let open_project = MenuItem::with_id(app, "open-project", "Open Project...", true, Some("Ctrl+Cmd+O"))?;
-    
+    let open_project = MenuItem::with_id(
+        app,
+        "open-project",
+        "Open Project...",
+        true,
+        Some("Ctrl+Cmd+O"),
+    )?;
+
     let file_menu = SubmenuBuilder::new(app, "File")
         .item(&open_project)
         .separator()
@@ -406,9 +849,7 @@ fn build_app_menu(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         .select_all()
         .build()?;
 
-    let view_menu = SubmenuBuilder::new(app, "View")
-        .fullscreen()
-        .build()?;
+    let view_menu = SubmenuBuilder::new(app, "View").fullscreen().build()?;
 
     let window_menu = SubmenuBuilder::new(app, "Window")
         .minimize()
@@ -419,7 +860,7 @@ fn build_app_menu(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
 
     #[cfg(debug_assertions)]
     let reload_item = MenuItem::with_id(app, "reload", "Reload", true, Some("CmdOrCtrl+R"))?;
-    
+
     #[cfg(debug_assertions)]
     let debug_menu = SubmenuBuilder::new(app, "Debug")
         .item(&reload_item)
@@ -470,17 +911,15 @@ pub fn run() {
             }
 
             // Handle menu events
-            app.on_menu_event(|app_handle, event| {
-                match event.id().0.as_str() {
-                    "open-project" => handle_open_project(app_handle),
-                    #[cfg(debug_assertions)]
-                    "reload" => {
-                        if let Some(window) = app_handle.get_webview_window("main") {
-                            let _ = window.eval("window.location.reload()");
-                        }
+            app.on_menu_event(|app_handle, event| match event.id().0.as_str() {
+                "open-project" => handle_open_project(app_handle),
+                #[cfg(debug_assertions)]
+                "reload" => {
+                    if let Some(window) = app_handle.get_webview_window("main") {
+                        let _ = window.eval("window.location.reload()");
                     }
-                    _ => {}
                 }
+                _ => {}
             });
 
             Ok(())
@@ -488,16 +927,29 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             find_repository,
             get_revisions,
+            get_revisions_page,
             get_status,
             get_file_diff,
+            get_file_bytes,
             get_revision_diff,
+            get_revision_hunks,
+            get_revision_parent_diffs,
             get_revision_changes,
+            get_revision_stats,
+            get_interdiff,
+            get_range_diff,
+            get_range_changes,
             get_commit_recency,
+            get_evolog,
             resolve_revset,
+            complete_revset,
             get_projects,
             upsert_project,
             find_project_by_path,
             remove_project,
+            get_revset_presets,
+            upsert_revset_preset,
+            remove_revset_preset,
             get_layout,
             update_layout,
             watch_repository,
@@ -506,6 +958,10 @@ pub fn run() {
             jj_new,
             jj_edit,
             jj_abandon,
+            jj_next,
+            jj_prev,
+            get_divergent_copies,
+            jj_resolve_divergence,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,16 +1,1868 @@
 use anyhow::Result;
-use similar::TextDiff;
+use imara_diff::intern::InternedInput;
+use jj_lib::backend::{BackendError, FileId, TreeValue};
+use jj_lib::commit::Commit;
+use jj_lib::copies::{CopyOperation, CopyRecords};
+use jj_lib::diff::{
+    CompareBytesExactly, CompareBytesIgnoreAllWhitespace, CompareBytesIgnoreWhitespaceAmount,
+    ContentDiff, DiffHunkKind, find_line_ranges,
+};
+use jj_lib::matchers::Matcher;
+use jj_lib::merge::MergedTreeValue;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::object_id::ObjectId;
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::{RepoPath, RepoPathBuf};
+use jj_lib::rewrite::rebase_to_dest_parent;
+use jj_lib::store::Store;
+use similar::{
+    Algorithm, ChangeTag, DiffOp, DiffTag, TextDiff, capture_diff_slices, group_diff_ops,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+
+use super::log::format_change_id;
+use super::status::ChangedFile;
+
+/// Line diff algorithm
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    #[default]
+    Myers,
+    Patience,
+    /// Git's histogram diff, an extension of patience that also anchors on
+    /// low-occurrence lines that aren't unique
+    Histogram,
+    /// jj's own content diff, as used by `jj diff`. It anchors on lines that are
+    /// rare on both sides, so it behaves like patience/histogram but is neither.
+    Jj,
+}
+
+/// How whitespace is treated when matching lines
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitespaceMode {
+    #[default]
+    Exact,
+    /// Runs of whitespace compare equal to a single space (`git diff -b`)
+    IgnoreAmount,
+    /// Whitespace is ignored entirely (`git diff -w`)
+    IgnoreAll,
+}
+
+/// Options for line diffs, stored per project
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DiffOptions {
+    /// Unchanged lines shown around each change
+    pub context: usize,
+    pub whitespace: WhitespaceMode,
+    /// Drop hunks whose changed lines are all blank
+    pub ignore_blank_lines: bool,
+    pub algorithm: DiffAlgorithm,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context: 3,
+            whitespace: WhitespaceMode::default(),
+            ignore_blank_lines: false,
+            algorithm: DiffAlgorithm::default(),
+        }
+    }
+}
+
+pub fn compute_file_diff(
+    old_content: &[u8],
+    new_content: &[u8],
+    path: &str,
+    options: &DiffOptions,
+) -> Result<String> {
+    Ok(unified_file_diff(
+        old_content,
+        new_content,
+        path,
+        path,
+        (None, None),
+        options,
+    ))
+}
+
+/// Unified diff of one file, possibly renamed from `old_path`. `modes` are the
+/// `value_mode`s of both sides, `None` where the file is absent; without either (plain
+/// contents) both sides are taken to exist. Like git, a mode change gets `old mode`/
+/// `new mode` lines, an added or deleted file is compared with /dev/null under a
+/// `new file mode`/`deleted file mode` line, and a change between file, symlink and
+/// submodule is shown as a deletion followed by an addition. When only metadata
+/// changed (a rename or mode change without content changes) it is just the header.
+fn unified_file_diff(
+    old_content: &[u8],
+    new_content: &[u8],
+    old_path: &str,
+    new_path: &str,
+    modes: (Option<&str>, Option<&str>),
+    options: &DiffOptions,
+) -> String {
+    if is_type_change(modes.0, modes.1) {
+        let deleted = unified_file_diff(
+            old_content,
+            b"",
+            old_path,
+            old_path,
+            (modes.0, None),
+            options,
+        );
+        let added = unified_file_diff(
+            b"",
+            new_content,
+            new_path,
+            new_path,
+            (None, modes.1),
+            options,
+        );
+        return deleted + &added;
+    }
+
+    let metadata_changed = old_path != new_path || modes.0 != modes.1;
+    let mut old_name = format!("a/{}", old_path);
+    let mut new_name = format!("b/{}", new_path);
+    let mut header = String::new();
+    match (modes.0, modes.1) {
+        (None, Some(new_mode)) => {
+            if let Some(new_mode) = git_mode(new_mode) {
+                header.push_str(&format!("new file mode {}\n", new_mode));
+            }
+            old_name = "/dev/null".to_string();
+        }
+        (Some(old_mode), None) => {
+            if let Some(old_mode) = git_mode(old_mode) {
+                header.push_str(&format!("deleted file mode {}\n", old_mode));
+            }
+            new_name = "/dev/null".to_string();
+        }
+        (Some(old_mode), Some(new_mode)) => {
+            if let (Some(old_mode), Some(new_mode)) = (git_mode(old_mode), git_mode(new_mode))
+                && old_mode != new_mode
+            {
+                header.push_str(&format!("old mode {}\nnew mode {}\n", old_mode, new_mode));
+            }
+        }
+        (None, None) => {}
+    }
+    header.push_str(&format!("--- {}\n+++ {}\n", old_name, new_name));
+    let header_only = || {
+        if metadata_changed {
+            header.clone()
+        } else {
+            String::new()
+        }
+    };
+
+    if is_binary(old_content) || is_binary(new_content) {
+        if old_content == new_content {
+            return header_only();
+        }
+        return format!(
+            "{}Binary files {} and {} differ\n",
+            header, old_name, new_name
+        );
+    }
+
+    let hunks = compute_file_hunks(old_content, new_content, options, InlineGranularity::None);
+    if hunks.is_empty() {
+        return header_only();
+    }
+
+    let mut unified = header;
+    for hunk in &hunks {
+        unified.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(hunk.old_start, hunk.old_lines),
+            hunk_range(hunk.new_start, hunk.new_lines)
+        ));
+        for line in &hunk.lines {
+            let prefix = match line.kind.as_str() {
+                "added" => '+',
+                "removed" => '-',
+                _ => ' ',
+            };
+            unified.push(prefix);
+            unified.push_str(&line.content);
+            unified.push('\n');
+            if line.missing_newline {
+                unified.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    unified
+}
+
+/// Whether content looks binary: a NUL byte within the first 8000 bytes, as git checks
+pub fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(8000)].contains(&0)
+}
+
+/// Range part of a unified diff hunk header; a length of 1 is implied
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, len)
+    }
+}
+
+/// One line of a hunk
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DiffLine {
+    /// "context", "added" or "removed"
+    pub kind: String,
+    /// Line text without its trailing newline
+    pub content: String,
+    /// 1-based line number in the old file (None for added lines)
+    pub old_line: Option<usize>,
+    /// 1-based line number in the new file (None for removed lines)
+    pub new_line: Option<usize>,
+    /// The line is the last in its file and has no trailing newline
+    pub missing_newline: bool,
+    /// Changed parts of a removed/added line relative to the line it replaced.
+    /// Empty for context lines and for lines with nothing in common.
+    pub inline_ranges: Vec<InlineRange>,
+    /// Set when the line belongs to a block that was moved rather than changed
+    pub moved: Option<LineMove>,
+}
+
+/// Where a moved line went to (removed lines) or came from (added lines)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LineMove {
+    /// Shared by both sides of one moved block, unique within a diff
+    pub id: usize,
+    pub path: String,
+    /// 1-based line number on the other side: in the new file for removed lines,
+    /// in the old file for added lines
+    pub line: usize,
+}
+
+/// Range into `DiffLine::content`, in UTF-16 code units like JS string indices
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct InlineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Granularity of intra-line change ranges
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InlineGranularity {
+    #[default]
+    Word,
+    Char,
+    None,
+}
+
+/// A hunk with line ranges as in a unified diff header (`@@ -old_start,old_lines +new_start,new_lines @@`)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Structured diff of one file
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// See `ChangedFile::status`
+    pub status: String,
+    /// Source of a rename or copy
+    pub source_path: Option<String>,
+    /// Content similarity to `source_path` in percent
+    pub similarity: Option<u8>,
+    /// Kind before and after (see `value_mode`), `None` where absent
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    pub hunks: Vec<DiffHunk>,
+    /// Set instead of `hunks` when either side is binary
+    pub binary: Option<BinaryDiff>,
+}
+
+/// Sizes and content hashes of a binary file; `None` where that side isn't a file
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BinaryDiff {
+    pub old_size: Option<usize>,
+    pub new_size: Option<usize>,
+    /// Backend file id, hex-encoded (the blob hash in git-backed repos)
+    pub old_hash: Option<String>,
+    pub new_hash: Option<String>,
+}
+
+/// Side of a diff, for fetching raw file contents
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffSide {
+    Old,
+    New,
+}
+
+/// Split the line diff of two file contents into hunks.
+pub fn compute_file_hunks(
+    old_content: &[u8],
+    new_content: &[u8],
+    options: &DiffOptions,
+    granularity: InlineGranularity,
+) -> Vec<DiffHunk> {
+    let old_text = String::from_utf8_lossy(old_content);
+    let new_text = String::from_utf8_lossy(new_content);
+    let old_lines: Vec<&str> = old_text.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new_text.split_inclusive('\n').collect();
+
+    let ops = match options.algorithm {
+        DiffAlgorithm::Myers => similar_line_ops(Algorithm::Myers, &old_lines, &new_lines, options),
+        DiffAlgorithm::Patience => {
+            similar_line_ops(Algorithm::Patience, &old_lines, &new_lines, options)
+        }
+        DiffAlgorithm::Histogram => histogram_line_ops(&old_lines, &new_lines, options),
+        DiffAlgorithm::Jj => jj_line_ops(&old_text, &new_text, options),
+    };
+
+    group_diff_ops(ops, options.context)
+        .iter()
+        .filter(|group| {
+            !options.ignore_blank_lines || !only_blank_changes(group, &old_lines, &new_lines)
+        })
+        .filter_map(|group| {
+            let (first, last) = (group.first()?, group.last()?);
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+
+            let mut lines: Vec<DiffLine> = group
+                .iter()
+                .flat_map(|op| op.iter_changes(&old_lines, &new_lines))
+                .map(|change| DiffLine {
+                    kind: match change.tag() {
+                        ChangeTag::Equal => "context",
+                        ChangeTag::Insert => "added",
+                        ChangeTag::Delete => "removed",
+                    }
+                    .to_string(),
+                    content: change
+                        .value()
+                        .strip_suffix('\n')
+                        .unwrap_or(change.value())
+                        .to_string(),
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    missing_newline: change.missing_newline(),
+                    inline_ranges: Vec::new(),
+                    moved: None,
+                })
+                .collect();
+            add_inline_ranges(&mut lines, granularity);
+
+            Some(DiffHunk {
+                old_start: hunk_start(&old_range),
+                old_lines: old_range.len(),
+                new_start: hunk_start(&new_range),
+                new_lines: new_range.len(),
+                lines,
+            })
+        })
+        .collect()
+}
+
+/// Line ops from one of `similar`'s algorithms, matching lines by their
+/// whitespace-normalized form.
+fn similar_line_ops(
+    algorithm: Algorithm,
+    old_lines: &[&str],
+    new_lines: &[&str],
+    options: &DiffOptions,
+) -> Vec<DiffOp> {
+    let key = |line: &&str| normalize_whitespace(line, options.whitespace);
+    let old_keys: Vec<String> = old_lines.iter().map(key).collect();
+    let new_keys: Vec<String> = new_lines.iter().map(key).collect();
+    capture_diff_slices(algorithm, &old_keys, &new_keys)
+}
+
+/// Line ops from imara-diff's histogram diff, matching lines by their
+/// whitespace-normalized form. It reports only the changed ranges, so the equal
+/// runs between them are filled in.
+fn histogram_line_ops(
+    old_lines: &[&str],
+    new_lines: &[&str],
+    options: &DiffOptions,
+) -> Vec<DiffOp> {
+    let key = |line: &&str| normalize_whitespace(line, options.whitespace);
+    let mut input = InternedInput::default();
+    input.update_before(old_lines.iter().map(key));
+    input.update_after(new_lines.iter().map(key));
+
+    let (mut old_index, mut new_index) = (0, 0);
+    let mut ops = Vec::new();
+    imara_diff::diff(
+        imara_diff::Algorithm::Histogram,
+        &input,
+        |old: Range<u32>, new: Range<u32>| {
+            let old = old.start as usize..old.end as usize;
+            let new = new.start as usize..new.end as usize;
+            if old.start > old_index {
+                ops.push(DiffOp::Equal {
+                    old_index,
+                    new_index,
+                    len: old.start - old_index,
+                });
+            }
+            ops.push(if new.is_empty() {
+                DiffOp::Delete {
+                    old_index: old.start,
+                    old_len: old.len(),
+                    new_index: new.start,
+                }
+            } else if old.is_empty() {
+                DiffOp::Insert {
+                    old_index: old.start,
+                    new_index: new.start,
+                    new_len: new.len(),
+                }
+            } else {
+                DiffOp::Replace {
+                    old_index: old.start,
+                    old_len: old.len(),
+                    new_index: new.start,
+                    new_len: new.len(),
+                }
+            });
+            (old_index, new_index) = (old.end, new.end);
+        },
+    );
+    if old_index < old_lines.len() {
+        ops.push(DiffOp::Equal {
+            old_index,
+            new_index,
+            len: old_lines.len() - old_index,
+        });
+    }
+    ops
+}
+
+/// Line ops from jj's content diff, converted from its byte ranges.
+fn jj_line_ops(old_text: &str, new_text: &str, options: &DiffOptions) -> Vec<DiffOp> {
+    let inputs = [old_text.as_bytes(), new_text.as_bytes()];
+    let diff = match options.whitespace {
+        WhitespaceMode::Exact => {
+            ContentDiff::for_tokenizer(inputs, find_line_ranges, CompareBytesExactly)
+        }
+        WhitespaceMode::IgnoreAmount => {
+            ContentDiff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreWhitespaceAmount)
+        }
+        WhitespaceMode::IgnoreAll => {
+            ContentDiff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreAllWhitespace)
+        }
+    };
+
+    let (mut old_index, mut new_index) = (0, 0);
+    let mut ops = Vec::new();
+    for hunk in diff.hunk_ranges() {
+        let old_len = find_line_ranges(&inputs[0][hunk.ranges[0].clone()]).len();
+        let new_len = find_line_ranges(&inputs[1][hunk.ranges[1].clone()]).len();
+        let op = match hunk.kind {
+            DiffHunkKind::Matching => DiffOp::Equal {
+                old_index,
+                new_index,
+                len: old_len,
+            },
+            DiffHunkKind::Different if new_len == 0 => DiffOp::Delete {
+                old_index,
+                old_len,
+                new_index,
+            },
+            DiffHunkKind::Different if old_len == 0 => DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            },
+            DiffHunkKind::Different => DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            },
+        };
+        if old_len > 0 || new_len > 0 {
+            ops.push(op);
+        }
+        old_index += old_len;
+        new_index += new_len;
+    }
+    ops
+}
+
+/// Same normalization as jj's whitespace-insensitive byte comparisons
+fn normalize_whitespace(line: &str, mode: WhitespaceMode) -> String {
+    match mode {
+        WhitespaceMode::Exact => line.to_string(),
+        WhitespaceMode::IgnoreAll => line.chars().filter(|c| !c.is_ascii_whitespace()).collect(),
+        WhitespaceMode::IgnoreAmount => {
+            let mut normalized = String::with_capacity(line.len());
+            let mut prev_was_space = false;
+            for c in line.chars() {
+                let is_space = c.is_ascii_whitespace();
+                if !is_space {
+                    normalized.push(c);
+                } else if !prev_was_space {
+                    normalized.push(' ');
+                }
+                prev_was_space = is_space;
+            }
+            normalized
+        }
+    }
+}
+
+/// Whether every line a group adds or removes is blank
+fn only_blank_changes(group: &[DiffOp], old_lines: &[&str], new_lines: &[&str]) -> bool {
+    group.iter().all(|op| {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        tag == DiffTag::Equal
+            || (old_lines[old_range]
+                .iter()
+                .all(|line| line.trim().is_empty())
+                && new_lines[new_range]
+                    .iter()
+                    .all(|line| line.trim().is_empty()))
+    })
+}
+
+/// Mark what changed within each removed/added pair from `inline_pairs`.
+fn add_inline_ranges(lines: &mut [DiffLine], granularity: InlineGranularity) {
+    if granularity == InlineGranularity::None {
+        return;
+    }
+
+    for (removed, added) in inline_pairs(lines) {
+        let (old_ranges, new_ranges) =
+            inline_ranges(&lines[removed].content, &lines[added].content, granularity);
+        lines[removed].inline_ranges = old_ranges;
+        lines[added].inline_ranges = new_ranges;
+    }
+}
+
+/// Indices of removed/added lines compared for inline ranges: each run of removed
+/// lines pairs up in order with the added lines right after it.
+fn inline_pairs(lines: &[DiffLine]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let removed_start = i;
+        while i < lines.len() && lines[i].kind == "removed" {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].kind == "added" {
+            i += 1;
+        }
+        if removed_start == added_start {
+            // Nothing was removed. Step over a context line, but not past the end of
+            // an added-only run, which may be followed by a removed line.
+            if i == removed_start {
+                i += 1;
+            }
+            continue;
+        }
+
+        let count = (added_start - removed_start).min(i - added_start);
+        pairs.extend((0..count).map(|n| (removed_start + n, added_start + n)));
+    }
+    pairs
+}
+
+/// Lines longer than this many bytes get no inline ranges. Char diffs of minified or
+/// generated lines would otherwise dominate the time spent on the whole file.
+const MAX_INLINE_LINE_LEN: usize = 1000;
+
+/// Ranges (in UTF-16 code units) that differ between two versions of a line. Returns
+/// nothing when the lines share no tokens, since highlighting everything wouldn't help,
+/// or when either line is longer than [`MAX_INLINE_LINE_LEN`].
+fn inline_ranges(
+    old: &str,
+    new: &str,
+    granularity: InlineGranularity,
+) -> (Vec<InlineRange>, Vec<InlineRange>) {
+    if old.len() > MAX_INLINE_LINE_LEN || new.len() > MAX_INLINE_LINE_LEN {
+        return (Vec::new(), Vec::new());
+    }
+    let diff = match granularity {
+        InlineGranularity::Word => TextDiff::from_words(old, new),
+        InlineGranularity::Char => TextDiff::from_chars(old, new),
+        InlineGranularity::None => return (Vec::new(), Vec::new()),
+    };
+
+    let mut old_ranges: Vec<InlineRange> = Vec::new();
+    let mut new_ranges: Vec<InlineRange> = Vec::new();
+    let (mut old_pos, mut new_pos) = (0, 0);
+    let mut has_common = false;
+
+    let push = |ranges: &mut Vec<InlineRange>, start: usize, end: usize| match ranges.last_mut() {
+        Some(last) if last.end == start => last.end = end,
+        _ => ranges.push(InlineRange { start, end }),
+    };
+
+    for change in diff.iter_all_changes() {
+        let len = change.value().encode_utf16().count();
+        match change.tag() {
+            ChangeTag::Equal => {
+                has_common |= !change.value().trim().is_empty();
+                old_pos += len;
+                new_pos += len;
+            }
+            ChangeTag::Delete => {
+                push(&mut old_ranges, old_pos, old_pos + len);
+                old_pos += len;
+            }
+            ChangeTag::Insert => {
+                push(&mut new_ranges, new_pos, new_pos + len);
+                new_pos += len;
+            }
+        }
+    }
+
+    if !has_common {
+        return (Vec::new(), Vec::new());
+    }
+    (old_ranges, new_ranges)
+}
+
+/// 1-based start of a hunk range; empty ranges point at the line before, like unified diffs.
+fn hunk_start(range: &std::ops::Range<usize>) -> usize {
+    if range.is_empty() {
+        range.start
+    } else {
+        range.start + 1
+    }
+}
+
+/// Status of a path given its values before and after. Symlinks and submodules get the
+/// same statuses as files; their kind is in the modes.
+fn file_status(before: &MergedTreeValue, after: &MergedTreeValue) -> &'static str {
+    let (old_mode, new_mode) = (value_mode(before), value_mode(after));
+    match (old_mode, new_mode) {
+        _ if is_type_change(old_mode, new_mode) => "type_changed",
+        (None, _) => "added",
+        (_, None) => "deleted",
+        _ if old_mode != new_mode && file_id(before) == file_id(after) => "mode_changed",
+        _ => "modified",
+    }
+}
+
+/// Whether two `value_mode`s are different kinds of entry: file, symlink or submodule
+fn is_type_change(old_mode: Option<&str>, new_mode: Option<&str>) -> bool {
+    let kind = |mode| match mode {
+        Some("executable") => Some("file"),
+        mode => mode,
+    };
+    matches!(
+        (kind(old_mode), kind(new_mode)),
+        (Some(old), Some(new)) if old != new && old != "conflict" && new != "conflict"
+    )
+}
+
+/// Kind of a tree value: "file", "executable", "symlink", "submodule" or "conflict".
+/// `None` when absent.
+pub fn value_mode(value: &MergedTreeValue) -> Option<&'static str> {
+    if value.is_absent() {
+        return None;
+    }
+    Some(match value.as_normal() {
+        Some(TreeValue::File {
+            executable: true, ..
+        }) => "executable",
+        Some(TreeValue::File { .. }) => "file",
+        Some(TreeValue::Symlink(_)) => "symlink",
+        Some(TreeValue::GitSubmodule(_)) => "submodule",
+        Some(TreeValue::Tree(_)) | None => "conflict",
+    })
+}
+
+/// Git's octal mode for a `value_mode`
+fn git_mode(mode: &str) -> Option<&'static str> {
+    match mode {
+        "file" => Some("100644"),
+        "executable" => Some("100755"),
+        "symlink" => Some("120000"),
+        "submodule" => Some("160000"),
+        _ => None,
+    }
+}
+
+/// Size of a diff: files touched and lines added/removed
+#[derive(Clone, Copy, Debug, Default, serde::Serialize)]
+pub struct DiffStats {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Count inserted and deleted lines between two file contents, diffed with `options` so
+/// the counts match the hunks shown. Binary content has no lines.
+pub fn count_line_changes(
+    old_content: &[u8],
+    new_content: &[u8],
+    options: &DiffOptions,
+) -> (usize, usize) {
+    if is_binary(old_content) || is_binary(new_content) {
+        return (0, 0);
+    }
+
+    compute_file_hunks(old_content, new_content, options, InlineGranularity::None)
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .fold((0, 0), |(insertions, deletions), line| {
+            match line.kind.as_str() {
+                "added" => (insertions + 1, deletions),
+                "removed" => (insertions, deletions + 1),
+                _ => (insertions, deletions),
+            }
+        })
+}
+
+/// Read the content of a tree value. Symlinks read as their target and submodules as
+/// `Subproject commit <id>`, like git shows them. Absent and conflicted values read as empty.
+pub async fn read_file_value(
+    store: &Store,
+    path: &RepoPath,
+    value: &MergedTreeValue,
+) -> Result<Vec<u8>> {
+    match value.as_normal() {
+        Some(TreeValue::File { id, .. }) => {
+            let mut reader = store.read_file(path, id).await?;
+            let mut content = Vec::new();
+            reader.read_to_end(&mut content).await?;
+            Ok(content)
+        }
+        Some(TreeValue::Symlink(id)) => Ok(store.read_symlink(path, id).await?.into_bytes()),
+        Some(TreeValue::GitSubmodule(id)) => {
+            Ok(format!("Subproject commit {}\n", id.hex()).into_bytes())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Id of a tree value that is a plain (non-conflicted) file
+fn file_id(value: &MergedTreeValue) -> Option<&FileId> {
+    match value.as_normal() {
+        Some(TreeValue::File { id, .. }) => Some(id),
+        _ => None,
+    }
+}
+
+/// Similarity (percent) at which a deleted and an added file pair up as a rename, as in git
+const RENAME_THRESHOLD: u8 = 50;
+
+/// With more deleted or added files than this, only exact renames are detected
+const MAX_RENAME_CANDIDATES: usize = 100;
+
+/// Time spent diffing for similarity: per recorded copy, and for all pairs tried by
+/// rename detection together. Diffs past it finish coarsely and score lower.
+const SIMILARITY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// A path that differs between two trees. Renames and copies carry their source path,
+/// and `before` is then the source's value.
+pub struct TreeChange {
+    pub source: Option<RepoPathBuf>,
+    pub path: RepoPathBuf,
+    /// See `ChangedFile::status`
+    pub status: &'static str,
+    /// Content similarity to the source in percent, for renames and copies
+    pub similarity: Option<u8>,
+    pub before: MergedTreeValue,
+    pub after: MergedTreeValue,
+    /// Contents of `before` and `after` if they were already read to compare them
+    old_content: Option<Vec<u8>>,
+    new_content: Option<Vec<u8>>,
+}
+
+impl TreeChange {
+    /// Path the `before` value is read from
+    pub fn source_path(&self) -> &RepoPath {
+        self.source.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Contents of `before` and `after`, reusing what rename detection already read
+    pub async fn take_contents(&mut self, store: &Store) -> Result<(Vec<u8>, Vec<u8>)> {
+        let old_content = match self.old_content.take() {
+            Some(content) => content,
+            None => read_file_value(store, self.source_path(), &self.before).await?,
+        };
+        let new_content = match self.new_content.take() {
+            Some(content) => content,
+            None => read_file_value(store, &self.path, &self.after).await?,
+        };
+        Ok((old_content, new_content))
+    }
+}
+
+/// Copies and renames the backend recorded from any of `sources` to `target`. Backends
+/// without copy tracking yield none, and similarity detection covers those.
+pub fn copy_records(sources: &[Commit], target: &Commit) -> Result<CopyRecords> {
+    use futures::StreamExt;
+
+    let store = target.store();
+    let mut records = CopyRecords::default();
+    for source in sources {
+        if source.id() == store.root_commit_id() {
+            continue;
+        }
+        let stream = match store.get_copy_records(None, source.id(), target.id()) {
+            Ok(stream) => stream,
+            Err(BackendError::Unsupported(_)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        records.add_records(pollster::block_on(stream.collect::<Vec<_>>()))?;
+    }
+    Ok(records)
+}
+
+/// Copy records from a commit's parents to the commit
+pub fn parent_copy_records(commit: &Commit) -> Result<CopyRecords> {
+    let parents: Vec<Commit> = commit.parents().collect::<Result<_, _>>()?;
+    copy_records(&parents, commit)
+}
+
+/// Changed paths between two trees, with renames and copies paired with their source:
+/// from `copy_records` where the backend tracks them, otherwise by content similarity.
+pub async fn collect_tree_changes(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_records: &CopyRecords,
+) -> Result<Vec<TreeChange>> {
+    use futures::StreamExt;
+
+    let store = to_tree.store();
+    let mut diff_iter = from_tree.diff_stream_with_copies(to_tree, matcher, copy_records);
+
+    let mut changes = Vec::new();
+    while let Some(entry) = diff_iter.next().await {
+        let values = entry.values?;
+        let (source, status) = match entry.path.source {
+            Some((source, CopyOperation::Rename)) => (Some(source), "renamed"),
+            Some((source, CopyOperation::Copy)) => (Some(source), "copied"),
+            None => (None, file_status(&values.before, &values.after)),
+        };
+
+        let mut change = TreeChange {
+            source,
+            path: entry.path.target,
+            status,
+            similarity: None,
+            before: values.before,
+            after: values.after,
+            old_content: None,
+            new_content: None,
+        };
+        if change.source.is_some() {
+            let (old_content, new_content) = change.take_contents(store).await?;
+            let deadline = Instant::now() + SIMILARITY_TIMEOUT;
+            change.similarity = Some(similarity(&old_content, &new_content, deadline));
+            change.old_content = Some(old_content);
+            change.new_content = Some(new_content);
+        }
+        changes.push(change);
+    }
+
+    detect_renames(store, &mut changes).await?;
+    Ok(changes)
+}
+
+/// Pair deleted files with added files of similar content as renames, best matches first.
+async fn detect_renames(store: &Store, changes: &mut Vec<TreeChange>) -> Result<()> {
+    let deleted: Vec<usize> = (0..changes.len())
+        .filter(|&i| changes[i].status == "deleted" && file_id(&changes[i].before).is_some())
+        .collect();
+    let added: Vec<usize> = (0..changes.len())
+        .filter(|&i| changes[i].status == "added" && file_id(&changes[i].after).is_some())
+        .collect();
+    if deleted.is_empty() || added.is_empty() {
+        return Ok(());
+    }
+
+    // (similarity, deleted index, added index)
+    let mut candidates = Vec::new();
+    if deleted.len() > MAX_RENAME_CANDIDATES || added.len() > MAX_RENAME_CANDIDATES {
+        let mut deleted_by_id: HashMap<FileId, usize> = HashMap::new();
+        for &d in &deleted {
+            if let Some(id) = file_id(&changes[d].before) {
+                deleted_by_id.entry(id.clone()).or_insert(d);
+            }
+        }
+        for &a in &added {
+            if let Some(&d) = file_id(&changes[a].after).and_then(|id| deleted_by_id.get(id)) {
+                candidates.push((100, d, a));
+            }
+        }
+    } else {
+        for &d in &deleted {
+            let content = read_file_value(store, &changes[d].path, &changes[d].before).await?;
+            changes[d].old_content = Some(content);
+        }
+        for &a in &added {
+            let content = read_file_value(store, &changes[a].path, &changes[a].after).await?;
+            changes[a].new_content = Some(content);
+        }
+        let deadline = Instant::now() + SIMILARITY_TIMEOUT;
+        for &d in &deleted {
+            for &a in &added {
+                let old_content = changes[d].old_content.as_deref().unwrap_or_default();
+                let new_content = changes[a].new_content.as_deref().unwrap_or_default();
+                // Empty files are all alike, so pairing them would be arbitrary
+                if old_content.is_empty() || new_content.is_empty() {
+                    continue;
+                }
+                // Like git, skip pairs whose sizes alone rule out reaching the threshold
+                let smaller = old_content.len().min(new_content.len());
+                let larger = old_content.len().max(new_content.len());
+                if smaller * 100 < larger * RENAME_THRESHOLD as usize {
+                    continue;
+                }
+                let score = similarity(old_content, new_content, deadline);
+                if score >= RENAME_THRESHOLD {
+                    candidates.push((score, d, a));
+                }
+            }
+        }
+    }
+    candidates.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+
+    let mut paired = HashSet::new();
+    let mut renamed_sources = HashSet::new();
+    for (score, d, a) in candidates {
+        if renamed_sources.contains(&d) || paired.contains(&a) {
+            continue;
+        }
+        renamed_sources.insert(d);
+        paired.insert(a);
+
+        let source = changes[d].path.clone();
+        let before = changes[d].before.clone();
+        let old_content = changes[d].old_content.take();
+        let change = &mut changes[a];
+        change.source = Some(source);
+        change.status = "renamed";
+        change.similarity = Some(score);
+        change.before = before;
+        change.old_content = old_content;
+    }
+
+    let mut index = 0;
+    changes.retain(|_| {
+        let keep = !renamed_sources.contains(&index);
+        index += 1;
+        keep
+    });
+    Ok(())
+}
+
+/// Content similarity in percent: line-based for text, exact matches only for binaries.
+/// Past `deadline` the line diff stops looking for more matching lines.
+fn similarity(old_content: &[u8], new_content: &[u8], deadline: Instant) -> u8 {
+    if old_content == new_content {
+        return 100;
+    }
+    if is_binary(old_content) || is_binary(new_content) {
+        return 0;
+    }
 
-pub fn compute_file_diff(old_content: &[u8], new_content: &[u8], path: &str) -> Result<String> {
     let old_text = String::from_utf8_lossy(old_content);
     let new_text = String::from_utf8_lossy(new_content);
+    let ratio = TextDiff::configure()
+        .deadline(deadline)
+        .diff_lines(&old_text, &new_text)
+        .ratio();
+    // Round down so only identical content counts as 100%
+    ((ratio * 100.0) as u8).min(99)
+}
+
+/// Compute the unified diff of every file that differs between two trees.
+pub fn compute_tree_diff(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_records: &CopyRecords,
+    options: &DiffOptions,
+) -> Result<String> {
+    let store = to_tree.store();
+
+    pollster::block_on(async {
+        let mut unified_diffs = Vec::new();
+        for mut change in collect_tree_changes(from_tree, to_tree, matcher, copy_records).await? {
+            let (old_content, new_content) = change.take_contents(store).await?;
+
+            let file_diff = unified_file_diff(
+                &old_content,
+                &new_content,
+                change.source_path().as_internal_file_string(),
+                change.path.as_internal_file_string(),
+                (value_mode(&change.before), value_mode(&change.after)),
+                options,
+            );
+            if !file_diff.is_empty() {
+                unified_diffs.push(file_diff);
+            }
+        }
+        Ok(unified_diffs.join("\n"))
+    })
+}
+
+/// Structured diff of every file that differs between two trees.
+pub fn compute_structured_tree_diff(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_records: &CopyRecords,
+    options: &DiffOptions,
+    granularity: InlineGranularity,
+) -> Result<Vec<FileDiff>> {
+    let store = to_tree.store();
+
+    pollster::block_on(async {
+        let mut files = Vec::new();
+        for mut change in collect_tree_changes(from_tree, to_tree, matcher, copy_records).await? {
+            let (old_content, new_content) = change.take_contents(store).await?;
+
+            let binary = (is_binary(&old_content) || is_binary(&new_content)).then(|| BinaryDiff {
+                old_size: file_id(&change.before).map(|_| old_content.len()),
+                new_size: file_id(&change.after).map(|_| new_content.len()),
+                old_hash: file_id(&change.before).map(|id| id.hex()),
+                new_hash: file_id(&change.after).map(|id| id.hex()),
+            });
+            let hunks = if binary.is_some() {
+                Vec::new()
+            } else {
+                compute_file_hunks(&old_content, &new_content, options, granularity)
+            };
+
+            files.push(FileDiff {
+                path: change.path.as_internal_file_string().to_string(),
+                status: change.status.to_string(),
+                source_path: change
+                    .source
+                    .as_ref()
+                    .map(|source| source.as_internal_file_string().to_string()),
+                similarity: change.similarity,
+                old_mode: value_mode(&change.before).map(String::from),
+                new_mode: value_mode(&change.after).map(String::from),
+                hunks,
+                binary,
+            });
+        }
+        mark_moved_lines(&mut files, options.whitespace);
+        Ok(files)
+    })
+}
+
+/// Fewest lines a block needs to be reported as moved
+const MIN_MOVED_LINES: usize = 3;
+
+/// Fewest alphanumeric characters a moved block needs, as in git's `--color-moved`
+const MIN_MOVED_ALNUM: usize = 20;
+
+/// Removed lines with the same content considered as the start of a moved block
+const MAX_MOVE_CANDIDATES: usize = 100;
+
+/// Pair blocks of removed lines with identical blocks of added lines elsewhere in the
+/// diff, in the same file or another one, and mark both sides as moved. Each added
+/// block takes the longest unclaimed removed block it matches.
+fn mark_moved_lines(files: &mut [FileDiff], whitespace: WhitespaceMode) {
+    // (file, hunk, line) indices of every line of one kind, in diff order
+    let positions = |kind: &str| -> Vec<(usize, usize, usize)> {
+        files
+            .iter()
+            .enumerate()
+            .flat_map(|(f, file)| {
+                file.hunks.iter().enumerate().flat_map(move |(h, hunk)| {
+                    hunk.lines
+                        .iter()
+                        .enumerate()
+                        .filter(move |(_, line)| line.kind == kind)
+                        .map(move |(l, _)| (f, h, l))
+                })
+            })
+            .collect()
+    };
+    let removed = positions("removed");
+    let added = positions("added");
+
+    let line = |(f, h, l): (usize, usize, usize)| &files[f].hunks[h].lines[l];
+    let keys = |positions: &[(usize, usize, usize)]| -> Vec<String> {
+        positions
+            .iter()
+            .map(|&pos| normalize_whitespace(&line(pos).content, whitespace))
+            .collect()
+    };
+    let (removed_keys, added_keys) = (keys(&removed), keys(&added));
+
+    // Whether the line at `i` directly follows the one before it in the same file
+    let contiguous = |positions: &[(usize, usize, usize)], i: usize| {
+        let number = |pos| {
+            let line = line(pos);
+            line.old_line.or(line.new_line)
+        };
+        positions[i].0 == positions[i - 1].0
+            && number(positions[i]) == number(positions[i - 1]).map(|n| n + 1)
+    };
+
+    let mut starts: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, key) in removed_keys.iter().enumerate() {
+        starts.entry(key.as_str()).or_default().push(i);
+    }
+
+    let mut claimed = vec![false; removed.len()];
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < added.len() {
+        let candidates = starts.get(added_keys[i].as_str()).into_iter().flatten();
+        let (len, start) = candidates
+            .take(MAX_MOVE_CANDIDATES)
+            .map(|&start| {
+                let len = (0..)
+                    .take_while(|&k| {
+                        i + k < added.len()
+                            && start + k < removed.len()
+                            && !claimed[start + k]
+                            && added_keys[i + k] == removed_keys[start + k]
+                            && (k == 0
+                                || contiguous(&added, i + k) && contiguous(&removed, start + k))
+                    })
+                    .count();
+                (len, start)
+            })
+            // First of the longest matches
+            .min_by_key(|&(len, _)| std::cmp::Reverse(len))
+            .unwrap_or((0, 0));
+
+        let alnum: usize = added_keys[i..i + len]
+            .iter()
+            .map(|key| key.chars().filter(|c| c.is_alphanumeric()).count())
+            .sum();
+        if len >= MIN_MOVED_LINES && alnum >= MIN_MOVED_ALNUM {
+            claimed[start..start + len].fill(true);
+            blocks.push((i, start, len));
+            i += len;
+        } else {
+            i += 1;
+        }
+    }
+
+    for (id, (added_start, removed_start, len)) in blocks.into_iter().enumerate() {
+        for k in 0..len {
+            let (af, ah, al) = added[added_start + k];
+            let (rf, rh, rl) = removed[removed_start + k];
+            let old_path = files[rf]
+                .source_path
+                .as_ref()
+                .unwrap_or(&files[rf].path)
+                .clone();
+            let new_path = files[af].path.clone();
+            let old_line = files[rf].hunks[rh].lines[rl].old_line.unwrap_or_default();
+            let new_line = files[af].hunks[ah].lines[al].new_line.unwrap_or_default();
+
+            files[af].hunks[ah].lines[al].moved = Some(LineMove {
+                id,
+                path: old_path,
+                line: old_line,
+            });
+            files[rf].hunks[rh].lines[rl].moved = Some(LineMove {
+                id,
+                path: new_path,
+                line: new_line,
+            });
+        }
+    }
+
+    // Inline ranges compare a line with its neighbour, which is meaningless when either
+    // of the two moved, so drop them from both lines of such pairs
+    for hunk in files.iter_mut().flat_map(|file| &mut file.hunks) {
+        for (removed, added) in inline_pairs(&hunk.lines) {
+            if hunk.lines[removed].moved.is_some() || hunk.lines[added].moved.is_some() {
+                hunk.lines[removed].inline_ranges.clear();
+                hunk.lines[added].inline_ranges.clear();
+            }
+        }
+    }
+}
+
+/// List the files that differ between two trees, pairing up renames and copies,
+/// with the lines added and removed in each.
+pub fn compute_changed_files(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_records: &CopyRecords,
+    options: &DiffOptions,
+) -> Result<Vec<ChangedFile>> {
+    let store = to_tree.store();
+
+    pollster::block_on(async {
+        let mut files = Vec::new();
+        for mut change in collect_tree_changes(from_tree, to_tree, matcher, copy_records).await? {
+            let (old_content, new_content) = change.take_contents(store).await?;
+            let (insertions, deletions) = count_line_changes(&old_content, &new_content, options);
+
+            files.push(ChangedFile {
+                path: change.path.as_internal_file_string().to_string(),
+                status: change.status.to_string(),
+                source_path: change
+                    .source
+                    .as_ref()
+                    .map(|source| source.as_internal_file_string().to_string()),
+                similarity: change.similarity,
+                old_mode: value_mode(&change.before).map(String::from),
+                new_mode: value_mode(&change.after).map(String::from),
+                insertions,
+                deletions,
+            });
+        }
+        Ok(files)
+    })
+}
+
+/// Count the files and lines that differ between two trees. Meant for many commits at
+/// once (e.g. log rows), so renames aren't detected and each file is read only once: a
+/// renamed file counts as one deletion and one addition.
+pub fn compute_tree_stats(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    options: &DiffOptions,
+) -> Result<DiffStats> {
+    use futures::StreamExt;
+
+    let store = to_tree.store();
+
+    pollster::block_on(async {
+        let mut stats = DiffStats::default();
+        let mut diff_iter = from_tree.diff_stream(to_tree, matcher);
+        while let Some(entry) = diff_iter.next().await {
+            let values = entry.values?;
+            let old_content = read_file_value(store, &entry.path, &values.before).await?;
+            let new_content = read_file_value(store, &entry.path, &values.after).await?;
+
+            let (insertions, deletions) = count_line_changes(&old_content, &new_content, options);
+            stats.files_changed += 1;
+            stats.insertions += insertions;
+            stats.deletions += deletions;
+        }
+        Ok(stats)
+    })
+}
+
+/// Totals of changed files from `compute_changed_files`, so they agree with the list
+pub fn changed_files_stats(files: &[ChangedFile]) -> DiffStats {
+    DiffStats {
+        files_changed: files.len(),
+        insertions: files.iter().map(|file| file.insertions).sum(),
+        deletions: files.iter().map(|file| file.deletions).sum(),
+    }
+}
+
+/// Diff of a commit against one of its parents
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ParentDiff {
+    pub parent_commit_id: String,
+    pub parent_change_id: String,
+    pub diff: String,
+}
+
+/// Diff a commit against each of its parents separately.
+pub fn compute_parent_diffs(
+    commit: &Commit,
+    matcher: &dyn Matcher,
+    options: &DiffOptions,
+) -> Result<Vec<ParentDiff>> {
+    let tree = commit.tree()?;
+    commit
+        .parents()
+        .map(|parent| {
+            let parent = parent?;
+            Ok(ParentDiff {
+                parent_commit_id: hex::encode(&parent.id().to_bytes()[..6]),
+                parent_change_id: format_change_id(parent.change_id()),
+                diff: compute_tree_diff(
+                    &parent.tree()?,
+                    &tree,
+                    matcher,
+                    &copy_records(std::slice::from_ref(&parent), commit)?,
+                    options,
+                )?,
+            })
+        })
+        .collect()
+}
+
+/// Diff two versions of the same change. `from` is rebased onto the parents of `to`
+/// in memory first, so only edits to the change itself show up, not upstream changes.
+pub fn compute_interdiff(
+    repo: &dyn Repo,
+    from: &Commit,
+    to: &Commit,
+    matcher: &dyn Matcher,
+    options: &DiffOptions,
+) -> Result<String> {
+    let from_tree = rebase_to_dest_parent(repo, std::slice::from_ref(from), to)?;
+    compute_tree_diff(
+        &from_tree,
+        &to.tree()?,
+        matcher,
+        &CopyRecords::default(),
+        options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jj_lib::backend::SymlinkId;
+
+    fn numbered_lines(range: std::ops::RangeInclusive<usize>) -> String {
+        range.map(|i| format!("line {}\n", i)).collect()
+    }
+
+    fn line_summary(hunk: &DiffHunk) -> Vec<(&str, Option<usize>, Option<usize>)> {
+        hunk.lines
+            .iter()
+            .map(|line| (line.kind.as_str(), line.old_line, line.new_line))
+            .collect()
+    }
+
+    #[test]
+    fn hunk_has_context_and_line_numbers() {
+        let old = numbered_lines(1..=10);
+        let new = old.replace("line 5\n", "line five\n");
+
+        let hunks = compute_file_hunks(
+            old.as_bytes(),
+            new.as_bytes(),
+            &DiffOptions::default(),
+            InlineGranularity::None,
+        );
+        assert_eq!(hunks.len(), 1);
+        let hunk = &hunks[0];
+        assert_eq!(
+            (
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines
+            ),
+            (2, 7, 2, 7)
+        );
+        assert_eq!(
+            line_summary(hunk),
+            vec![
+                ("context", Some(2), Some(2)),
+                ("context", Some(3), Some(3)),
+                ("context", Some(4), Some(4)),
+                ("removed", Some(5), None),
+                ("added", None, Some(5)),
+                ("context", Some(6), Some(6)),
+                ("context", Some(7), Some(7)),
+                ("context", Some(8), Some(8)),
+            ]
+        );
+        assert_eq!(hunk.lines[4].content, "line five");
+    }
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let old = numbered_lines(1..=20);
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line eighteen\n");
+
+        let hunks = compute_file_hunks(
+            old.as_bytes(),
+            new.as_bytes(),
+            &DiffOptions::default(),
+            InlineGranularity::None,
+        );
+        let starts: Vec<_> = hunks.iter().map(|hunk| hunk.old_start).collect();
+        assert_eq!(starts, vec![1, 15]);
+    }
+
+    #[test]
+    fn new_file_hunk_starts_at_zero() {
+        let hunks = compute_file_hunks(
+            b"",
+            b"a\nb\n",
+            &DiffOptions::default(),
+            InlineGranularity::None,
+        );
+        assert_eq!(hunks.len(), 1);
+        let hunk = &hunks[0];
+        assert_eq!(
+            (
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines
+            ),
+            (0, 0, 1, 2)
+        );
+    }
+
+    #[test]
+    fn missing_trailing_newline_is_flagged() {
+        let hunks = compute_file_hunks(
+            b"a\n",
+            b"a",
+            &DiffOptions::default(),
+            InlineGranularity::None,
+        );
+        let lines = &hunks[0].lines;
+        assert_eq!(
+            line_summary(&hunks[0]),
+            vec![("removed", Some(1), None), ("added", None, Some(1)),]
+        );
+        assert!(!lines[0].missing_newline);
+        assert!(lines[1].missing_newline);
+        assert_eq!(lines[1].content, "a");
+    }
+
+    #[test]
+    fn identical_content_has_no_hunks() {
+        assert!(
+            compute_file_hunks(
+                b"same\n",
+                b"same\n",
+                &DiffOptions::default(),
+                InlineGranularity::None
+            )
+            .is_empty()
+        );
+    }
+
+    fn line(kind: &str, content: &str) -> DiffLine {
+        DiffLine {
+            kind: kind.to_string(),
+            content: content.to_string(),
+            old_line: None,
+            new_line: None,
+            missing_newline: false,
+            inline_ranges: Vec::new(),
+            moved: None,
+        }
+    }
+
+    fn ranges(ranges: &[InlineRange]) -> Vec<(usize, usize)> {
+        ranges
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    #[test]
+    fn word_ranges_cover_changed_words() {
+        let (old, new) = inline_ranges("let x = 1;", "let y = 1;", InlineGranularity::Word);
+        assert_eq!(ranges(&old), vec![(4, 5)]);
+        assert_eq!(ranges(&new), vec![(4, 5)]);
+    }
+
+    #[test]
+    fn char_ranges_cover_changed_chars() {
+        let (old, new) = inline_ranges("color", "colour", InlineGranularity::Char);
+        assert!(old.is_empty());
+        assert_eq!(ranges(&new), vec![(4, 5)]);
+    }
+
+    #[test]
+    fn unrelated_lines_get_no_ranges() {
+        let (old, new) = inline_ranges("foo", "bar baz", InlineGranularity::Word);
+        assert!(old.is_empty() && new.is_empty());
+    }
+
+    #[test]
+    fn removed_and_added_lines_pair_up_in_order() {
+        let mut lines = vec![
+            line("context", "fn main() {"),
+            line("removed", "    let a = 1;"),
+            line("removed", "    let b = 2;"),
+            line("added", "    let a = 10;"),
+            line("added", "    let b = 20;"),
+            line("added", "    let c = 30;"),
+            line("context", "}"),
+        ];
+        add_inline_ranges(&mut lines, InlineGranularity::Word);
+
+        let all: Vec<_> = lines
+            .iter()
+            .map(|line| ranges(&line.inline_ranges))
+            .collect();
+        assert_eq!(
+            all,
+            vec![
+                vec![],
+                vec![(12, 14)],
+                vec![(12, 14)],
+                vec![(12, 15)],
+                vec![(12, 15)],
+                vec![],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_line_after_added_only_run_is_paired() {
+        let mut lines = vec![
+            line("context", "a"),
+            line("added", "new line"),
+            line("removed", "let x = 1;"),
+            line("added", "let x = 2;"),
+        ];
+        add_inline_ranges(&mut lines, InlineGranularity::Word);
+
+        assert_eq!(ranges(&lines[2].inline_ranges), vec![(8, 10)]);
+        assert_eq!(ranges(&lines[3].inline_ranges), vec![(8, 10)]);
+    }
+
+    #[test]
+    fn ranges_count_utf16_units() {
+        let (old, new) = inline_ranges("café 😀 x", "café 😀 y", InlineGranularity::Word);
+        assert_eq!(ranges(&old), vec![(8, 9)]);
+        assert_eq!(ranges(&new), vec![(8, 9)]);
+    }
+
+    #[test]
+    fn long_lines_get_no_ranges() {
+        let old = format!("{} x", "a".repeat(MAX_INLINE_LINE_LEN));
+        let new = format!("{} y", "a".repeat(MAX_INLINE_LINE_LEN));
+        let (old, new) = inline_ranges(&old, &new, InlineGranularity::Char);
+        assert!(old.is_empty() && new.is_empty());
+    }
+
+    #[test]
+    fn histogram_ops_fill_in_equal_runs() {
+        let old = ["a\n", "b\n", "c\n", "d\n"];
+        let new = ["a\n", "B\n", "c\n", "d\n", "e\n"];
+        let ops = histogram_line_ops(&old, &new, &DiffOptions::default());
+        assert_eq!(
+            ops,
+            vec![
+                DiffOp::Equal {
+                    old_index: 0,
+                    new_index: 0,
+                    len: 1
+                },
+                DiffOp::Replace {
+                    old_index: 1,
+                    old_len: 1,
+                    new_index: 1,
+                    new_len: 1
+                },
+                DiffOp::Equal {
+                    old_index: 2,
+                    new_index: 2,
+                    len: 2
+                },
+                DiffOp::Insert {
+                    old_index: 4,
+                    new_index: 4,
+                    new_len: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn line_counts_follow_options() {
+        let (old, new) = (b"a\n  b\n", b"a\n\tb\nc\n");
+        assert_eq!(
+            count_line_changes(old, new, &DiffOptions::default()),
+            (2, 1)
+        );
+        let options = DiffOptions {
+            whitespace: WhitespaceMode::IgnoreAll,
+            ..DiffOptions::default()
+        };
+        assert_eq!(count_line_changes(old, new, &options), (1, 0));
+    }
+
+    #[test]
+    fn whitespace_normalization() {
+        let line = "  a \t b\n";
+        assert_eq!(normalize_whitespace(line, WhitespaceMode::Exact), line);
+        assert_eq!(
+            normalize_whitespace(line, WhitespaceMode::IgnoreAmount),
+            " a b "
+        );
+        assert_eq!(normalize_whitespace(line, WhitespaceMode::IgnoreAll), "ab");
+    }
+
+    #[test]
+    fn ignored_whitespace_changes_produce_no_hunks() {
+        let options = DiffOptions {
+            whitespace: WhitespaceMode::IgnoreAmount,
+            ..DiffOptions::default()
+        };
+        let old = b"fn main() {\n    run();\n}\n";
+        let new = b"fn main() {\n\trun();\n}\n";
+        assert!(compute_file_hunks(old, new, &options, InlineGranularity::None).is_empty());
+        assert_eq!(
+            compute_file_hunks(old, new, &DiffOptions::default(), InlineGranularity::None).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn blank_line_hunks_can_be_dropped() {
+        let options = DiffOptions {
+            ignore_blank_lines: true,
+            ..DiffOptions::default()
+        };
+        assert!(
+            compute_file_hunks(b"a\nb\n", b"a\n\nb\n", &options, InlineGranularity::None)
+                .is_empty()
+        );
+    }
+
+    fn file_value(id: u8) -> MergedTreeValue {
+        MergedTreeValue::normal(TreeValue::File {
+            id: FileId::new(vec![id]),
+            executable: false,
+            copy_id: jj_lib::backend::CopyId::placeholder(),
+        })
+    }
+
+    #[test]
+    fn status_from_values() {
+        let absent = MergedTreeValue::absent();
+        assert_eq!(file_status(&absent, &file_value(1)), "added");
+        assert_eq!(file_status(&file_value(1), &absent), "deleted");
+        assert_eq!(file_status(&file_value(1), &file_value(2)), "modified");
+    }
+
+    fn far_deadline() -> Instant {
+        Instant::now() + Duration::from_secs(60)
+    }
+
+    #[test]
+    fn similarity_of_text() {
+        let old = numbered_lines(1..=10);
+        let mut new = numbered_lines(1..=9);
+        new.push_str("changed\n");
+
+        assert_eq!(
+            similarity(old.as_bytes(), old.as_bytes(), far_deadline()),
+            100
+        );
+        let score = similarity(old.as_bytes(), new.as_bytes(), far_deadline());
+        assert!((RENAME_THRESHOLD..100).contains(&score), "{}", score);
+        assert!(similarity(b"a\nb\n", b"c\nd\n", far_deadline()) < RENAME_THRESHOLD);
+    }
+
+    #[test]
+    fn binaries_are_only_similar_when_identical() {
+        assert_eq!(similarity(b"\0abc", b"\0abc", far_deadline()), 100);
+        assert_eq!(similarity(b"\0abc", b"\0abd", far_deadline()), 0);
+    }
+
+    #[test]
+    fn status_of_mode_and_type_changes() {
+        let executable = MergedTreeValue::normal(TreeValue::File {
+            id: FileId::new(vec![1]),
+            executable: true,
+            copy_id: jj_lib::backend::CopyId::placeholder(),
+        });
+        let symlink = MergedTreeValue::normal(TreeValue::Symlink(SymlinkId::new(vec![1])));
+        let absent = MergedTreeValue::absent();
+
+        assert_eq!(value_mode(&executable), Some("executable"));
+        assert_eq!(value_mode(&absent), None);
+        assert_eq!(file_status(&file_value(1), &executable), "mode_changed");
+        assert_eq!(file_status(&file_value(1), &symlink), "type_changed");
+        assert_eq!(file_status(&absent, &symlink), "added");
+        assert_eq!(file_status(&symlink, &absent), "deleted");
+    }
+
+    #[test]
+    fn added_deleted_and_type_changes_get_git_headers() {
+        let options = DiffOptions::default();
+        assert_eq!(
+            unified_file_diff(b"", b"a\n", "x", "x", (None, Some("file")), &options),
+            "new file mode 100644\n--- /dev/null\n+++ b/x\n@@ -0,0 +1 @@\n+a\n"
+        );
+        assert_eq!(
+            unified_file_diff(b"a\n", b"", "x", "x", (Some("executable"), None), &options),
+            "deleted file mode 100755\n--- a/x\n+++ /dev/null\n@@ -1 +0,0 @@\n-a\n"
+        );
+        assert_eq!(
+            unified_file_diff(
+                b"a\n",
+                b"b",
+                "x",
+                "x",
+                (Some("file"), Some("symlink")),
+                &options
+            ),
+            "deleted file mode 100644\n--- a/x\n+++ /dev/null\n@@ -1 +0,0 @@\n-a\n\
+             new file mode 120000\n--- /dev/null\n+++ b/x\n@@ -0,0 +1 @@\n+b\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn mode_change_gets_git_mode_lines() {
+        let options = DiffOptions::default();
+        let modes = (Some("file"), Some("executable"));
+        assert_eq!(
+            unified_file_diff(b"a\n", b"a\n", "run.sh", "run.sh", modes, &options),
+            "old mode 100644\nnew mode 100755\n--- a/run.sh\n+++ b/run.sh\n"
+        );
+        assert!(
+            unified_file_diff(b"a\n", b"b\n", "run.sh", "run.sh", modes, &options)
+                .starts_with("old mode 100644\nnew mode 100755\n--- a/run.sh\n")
+        );
+        assert_eq!(
+            unified_file_diff(
+                b"a\n",
+                b"a\n",
+                "x",
+                "x",
+                (Some("file"), Some("file")),
+                &options
+            ),
+            ""
+        );
+    }
+
+    #[test]
+    fn binary_added_or_deleted_against_dev_null() {
+        let options = DiffOptions::default();
+        assert!(
+            unified_file_diff(b"", b"\0", "x", "x", (None, Some("file")), &options)
+                .ends_with("Binary files /dev/null and b/x differ\n")
+        );
+        assert!(
+            unified_file_diff(b"\0", b"", "x", "x", (Some("file"), None), &options)
+                .ends_with("Binary files a/x and /dev/null differ\n")
+        );
+    }
+
+    fn file_diff(path: &str, old: &str, new: &str) -> FileDiff {
+        FileDiff {
+            path: path.to_string(),
+            status: "modified".to_string(),
+            source_path: None,
+            similarity: None,
+            old_mode: None,
+            new_mode: None,
+            hunks: compute_file_hunks(
+                old.as_bytes(),
+                new.as_bytes(),
+                &DiffOptions::default(),
+                InlineGranularity::Word,
+            ),
+            binary: None,
+        }
+    }
+
+    /// Changed lines as "kind" or "kind #id path:line" for moved ones
+    fn moves(files: &[FileDiff]) -> Vec<String> {
+        files
+            .iter()
+            .flat_map(|file| file.hunks.iter().flat_map(|hunk| &hunk.lines))
+            .filter(|line| line.kind != "context")
+            .map(|line| match &line.moved {
+                Some(moved) => format!("{} #{} {}:{}", line.kind, moved.id, moved.path, moved.line),
+                None => line.kind.clone(),
+            })
+            .collect()
+    }
+
+    const BLOCK: &str = "fn helper_function() {\n    do_something();\n}\n";
+
+    #[test]
+    fn block_moved_within_a_file() {
+        let rest = numbered_lines(1..=10);
+        let mut files = vec![file_diff(
+            "a.rs",
+            &format!("{}{}", BLOCK, rest),
+            &format!("{}{}", rest, BLOCK),
+        )];
+        mark_moved_lines(&mut files, WhitespaceMode::Exact);
+
+        assert_eq!(
+            moves(&files),
+            vec![
+                "removed #0 a.rs:11",
+                "removed #0 a.rs:12",
+                "removed #0 a.rs:13",
+                "added #0 a.rs:1",
+                "added #0 a.rs:2",
+                "added #0 a.rs:3",
+            ]
+        );
+    }
+
+    #[test]
+    fn block_moved_between_files() {
+        let mut files = vec![
+            file_diff("a.rs", &format!("a\n{}", BLOCK), "a\n"),
+            file_diff("b.rs", "b\n", &format!("b\n{}", BLOCK)),
+        ];
+        mark_moved_lines(&mut files, WhitespaceMode::Exact);
+
+        assert_eq!(
+            moves(&files),
+            vec![
+                "removed #0 b.rs:2",
+                "removed #0 b.rs:3",
+                "removed #0 b.rs:4",
+                "added #0 a.rs:2",
+                "added #0 a.rs:3",
+                "added #0 a.rs:4",
+            ]
+        );
+    }
+
+    #[test]
+    fn short_blocks_are_not_moves() {
+        let mut files = vec![
+            file_diff("a.rs", "a\n}\n}\n", "a\n"),
+            file_diff("b.rs", "b\n", "b\n}\n}\n"),
+        ];
+        mark_moved_lines(&mut files, WhitespaceMode::Exact);
+
+        assert_eq!(moves(&files), vec!["removed", "removed", "added", "added"]);
+    }
 
-    let diff = TextDiff::from_lines(&old_text, &new_text);
-    let unified = diff
-        .unified_diff()
-        .header(&format!("a/{}", path), &format!("b/{}", path))
-        .context_radius(3)
-        .to_string();
+    #[test]
+    fn moved_lines_and_their_inline_partners_have_no_ranges() {
+        // The block moves to just before a changed line, so its first line gets paired
+        // with the removed version of that line
+        let rest = numbered_lines(1..=10);
+        let old = format!("{}{}fn other() {{\n", BLOCK, rest);
+        let new = format!("{}{}fn renamed() {{\n", rest, BLOCK);
+        let mut files = vec![file_diff("a.rs", &old, &new)];
+        let has_ranges = |files: &[FileDiff]| {
+            files[0]
+                .hunks
+                .iter()
+                .flat_map(|hunk| &hunk.lines)
+                .any(|line| !line.inline_ranges.is_empty())
+        };
+        assert!(has_ranges(&files));
 
-    Ok(unified)
+        mark_moved_lines(&mut files, WhitespaceMode::Exact);
+        assert!(!has_ranges(&files));
+    }
 }
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::object_id::ObjectId;
+use jj_lib::repo::Repo;
+use jj_lib::revset::RevsetExpression;
+use std::path::Path;
+
+use super::diff::{self, DiffOptions};
+use super::jj::JjRepo;
+
+/// One visible copy of a divergent change
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DivergentCopy {
+    pub commit_id: String,
+    pub description: String,
+    pub author: String,
+    pub timestamp: i64,
+    pub parent_ids: Vec<String>,
+    pub is_working_copy: bool,
+    /// Interdiff from the first copy to this one (empty for the first copy)
+    pub diff: String,
+}
+
+/// List the visible copies of a change in log order, each with its interdiff
+/// against the first copy.
+pub fn fetch_divergent_copies(
+    repo_path: &Path,
+    change_id: &str,
+    options: &DiffOptions,
+) -> Result<Vec<DivergentCopy>> {
+    let jj_repo = JjRepo::open(repo_path)?;
+    let repo = jj_repo.repo_loader().load_at_head()?;
+    let wc_id = repo.view().get_wc_commit_id(jj_repo.workspace_name());
+
+    let commit_ids = jj_repo
+        .get_commits_for_change(change_id)?
+        .iter()
+        .map(|commit| commit.id().clone())
+        .collect();
+
+    // Evaluate as a revset so copies come out in the same order as the log
+    let copies = RevsetExpression::commits(commit_ids)
+        .evaluate(repo.as_ref())?
+        .iter()
+        .map(|id| Ok(repo.store().get_commit(&id?)?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let Some(first) = copies.first() else {
+        return Ok(Vec::new());
+    };
+
+    copies
+        .iter()
+        .map(|commit| {
+            let diff = if commit.id() == first.id() {
+                String::new()
+            } else {
+                diff::compute_interdiff(repo.as_ref(), first, commit, &EverythingMatcher, options)?
+            };
+
+            Ok(DivergentCopy {
+                commit_id: hex::encode(&commit.id().to_bytes()[..6]),
+                description: commit.description().to_string(),
+                author: commit.author().name.clone(),
+                timestamp: commit.committer().timestamp.timestamp.0,
+                parent_ids: commit
+                    .parent_ids()
+                    .iter()
+                    .map(|id| hex::encode(&id.to_bytes()[..6]))
+                    .collect(),
+                is_working_copy: wc_id == Some(commit.id()),
+                diff,
+            })
+        })
+        .collect()
+}
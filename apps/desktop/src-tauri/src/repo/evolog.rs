@@ -0,0 +1,88 @@
+use anyhow::Result;
+use jj_lib::copies::CopyRecords;
+use jj_lib::evolution::walk_predecessors;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::object_id::ObjectId;
+use jj_lib::rewrite::rebase_to_dest_parent;
+use std::path::Path;
+
+use super::diff::{self, DiffOptions};
+use super::jj::JjRepo;
+use super::log::format_change_id;
+
+/// Operation that created or rewrote a version of a change
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EvologOperation {
+    pub id: String,
+    pub description: String,
+    pub timestamp: i64,
+}
+
+/// One version of a change in its evolution log
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EvologEntry {
+    pub commit_id: String,
+    pub change_id: String,
+    pub description: String,
+    pub author: String,
+    pub timestamp: i64,
+    pub predecessor_ids: Vec<String>,
+    pub operation: Option<EvologOperation>,
+    /// Unified diff from the previous version rebased onto this version's parents
+    /// (or from the parents for the first version)
+    pub diff: String,
+}
+
+/// Walk the predecessors of a change, newest version first.
+pub fn fetch_evolog(
+    repo_path: &Path,
+    change_id: &str,
+    limit: usize,
+    options: &DiffOptions,
+) -> Result<Vec<EvologEntry>> {
+    let jj_repo = JjRepo::open(repo_path)?;
+    let repo = jj_repo.repo_loader().load_at_head()?;
+    let commit = jj_repo.get_commit(change_id)?;
+
+    let mut entries = Vec::new();
+
+    for entry_result in walk_predecessors(&repo, std::slice::from_ref(commit.id())).take(limit) {
+        let entry =
+            entry_result.map_err(|e| anyhow::anyhow!("Failed to walk predecessors: {}", e))?;
+        let commit = &entry.commit;
+
+        // Rebase predecessors onto this version's parents so rebases don't show up as edits
+        let predecessors: Vec<_> = entry.predecessors().collect::<Result<_, _>>()?;
+        let from_tree = rebase_to_dest_parent(repo.as_ref(), &predecessors, commit)?;
+        let diff = diff::compute_tree_diff(
+            &from_tree,
+            &commit.tree()?,
+            &EverythingMatcher,
+            &CopyRecords::default(),
+            options,
+        )?;
+
+        let operation = entry.operation.as_ref().map(|op| EvologOperation {
+            id: hex::encode(&op.id().to_bytes()[..6]),
+            description: op.metadata().description.clone(),
+            timestamp: op.metadata().time.start.timestamp.0,
+        });
+
+        entries.push(EvologEntry {
+            commit_id: hex::encode(&commit.id().to_bytes()[..6]),
+            change_id: format_change_id(commit.change_id()),
+            description: commit.description().to_string(),
+            author: commit.author().name.clone(),
+            timestamp: commit.committer().timestamp.timestamp.0,
+            predecessor_ids: entry
+                .predecessor_ids()
+                .iter()
+                .map(|id| hex::encode(&id.to_bytes()[..6]))
+                .collect(),
+            operation,
+            diff,
+        });
+    }
+
+    Ok(entries)
+}
@@ -1,17 +1,44 @@
 use anyhow::{Context, Result};
 use jj_lib::backend::{ChangeId, CommitId};
 use jj_lib::commit::Commit;
-use jj_lib::config::ConfigSource;
+use jj_lib::config::{ConfigLayer, ConfigLoadError, ConfigSource, StackedConfig};
+use jj_lib::fileset::{self, FilesetDiagnostics, FilesetExpression};
+use jj_lib::matchers::{EverythingMatcher, Matcher};
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::{HexPrefix, ObjectId, PrefixResolution};
 use jj_lib::op_walk;
 use jj_lib::repo::{Repo, StoreFactories};
-use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::{RepoPath, RepoPathUiConverter};
+use jj_lib::revset::RevsetExpression;
+use jj_lib::rewrite::merge_commit_trees;
 use jj_lib::settings::UserSettings;
-use jj_lib::workspace::{Workspace, default_working_copy_factories};
+use jj_lib::transaction::Transaction;
+use jj_lib::workspace::{
+    DefaultWorkspaceLoaderFactory, Workspace, WorkspaceLoaderFactory,
+    default_working_copy_factories,
+};
 use std::collections::HashMap;
+use std::io;
 use std::path::Path;
-use tokio::io::AsyncReadExt;
+
+use super::diff;
+use super::revset::{IMMUTABLE_REVSET, RevsetEnvironment, TrunkSettings};
+
+/// Direction to move `@` in for `next` / `prev`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Next,
+    Prev,
+}
+
+/// Outcome of a `next` / `prev` move
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MovementResult {
+    /// Change ID of the new working copy, or None if the target was ambiguous
+    pub change_id: Option<String>,
+    /// Change IDs to choose from when more than one revision matched
+    pub candidates: Vec<String>,
+}
 
 pub struct JjRepo {
     workspace: Workspace,
@@ -19,21 +46,44 @@ pub struct JjRepo {
     user_settings: UserSettings,
 }
 
+/// Load a config file into `config` if it exists. Any other error, such as a syntax
+/// error, is reported rather than silently ignored.
+fn load_config_file(config: &mut StackedConfig, source: ConfigSource, path: &Path) -> Result<()> {
+    match config.load_file(source, path) {
+        Err(ConfigLoadError::Read(err)) if err.source.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result.with_context(|| format!("Failed to load {}", path.display())),
+    }
+}
+
 impl JjRepo {
     pub fn open(path: &Path) -> Result<Self> {
-        let config = Self::load_config()?;
+        Self::open_with_trunk(path, &TrunkSettings::default())
+    }
+
+    /// Open the repo with a per-project `trunk()` that takes precedence over jj config.
+    pub fn open_with_trunk(path: &Path, trunk: &TrunkSettings) -> Result<Self> {
+        // Locate the repo first so its config can be layered into the settings
+        let workspace_loader = DefaultWorkspaceLoaderFactory
+            .create(path)
+            .context("Failed to load jj workspace")?;
+
+        let mut config = Self::load_config(workspace_loader.repo_path())?;
+        if let Some(trunk_alias) = trunk.alias_definition() {
+            let mut project_layer = ConfigLayer::empty(ConfigSource::CommandArg);
+            project_layer
+                .set_value(["revset-aliases", "trunk()"], trunk_alias)
+                .context("Failed to set trunk() alias")?;
+            config.add_layer(project_layer);
+        }
+
         let user_settings = jj_lib::settings::UserSettings::from_config(config)
             .context("Failed to create user settings")?;
         let store_factories = StoreFactories::default();
         let working_copy_factories = default_working_copy_factories();
 
-        let workspace = Workspace::load(
-            &user_settings,
-            path,
-            &store_factories,
-            &working_copy_factories,
-        )
-        .context("Failed to load jj workspace")?;
+        let workspace = workspace_loader
+            .load(&user_settings, &store_factories, &working_copy_factories)
+            .context("Failed to load jj workspace")?;
 
         Ok(Self {
             workspace,
@@ -41,9 +91,7 @@ impl JjRepo {
         })
     }
 
-    fn load_config() -> Result<jj_lib::config::StackedConfig> {
-        use jj_lib::config::{ConfigLayer, StackedConfig};
-
+    fn load_config(repo_dir: &Path) -> Result<StackedConfig> {
         let mut config = StackedConfig::with_defaults();
 
         // Add environment-based defaults for operation metadata (matching jj-cli behavior)
@@ -63,17 +111,25 @@ impl JjRepo {
                 .map(std::path::PathBuf::from)
                 .unwrap_or_else(|_| Path::new(&home).join(".config"));
 
-            let jj_config = xdg_config.join("jj/config.toml");
-            if jj_config.exists() {
-                let _ = config.load_file(ConfigSource::User, &jj_config);
-            }
-
-            let legacy = Path::new(&home).join(".jjconfig.toml");
-            if legacy.exists() {
-                let _ = config.load_file(ConfigSource::User, &legacy);
-            }
+            load_config_file(
+                &mut config,
+                ConfigSource::User,
+                &xdg_config.join("jj/config.toml"),
+            )?;
+            load_config_file(
+                &mut config,
+                ConfigSource::User,
+                &Path::new(&home).join(".jjconfig.toml"),
+            )?;
         }
 
+        // Repo config (`jj config edit --repo`) takes precedence over user config
+        load_config_file(
+            &mut config,
+            ConfigSource::Repo,
+            &repo_dir.join("config.toml"),
+        )?;
+
         Ok(config)
     }
 
@@ -83,66 +139,43 @@ impl JjRepo {
         Ok(repo.store().get_commit(&commit_id)?)
     }
 
-    #[allow(dead_code)] // May be used in future features
+    /// Look up a commit (visible or hidden) by commit ID hex prefix.
+    pub fn get_commit_by_id(&self, commit_id: &str) -> Result<Commit> {
+        let repo = self.workspace.repo_loader().load_at_head()?;
+        let commit_id = self.resolve_commit_id(repo.as_ref(), commit_id)?;
+        Ok(repo.store().get_commit(&commit_id)?)
+    }
+
+    /// Get every visible commit of a change (more than one if the change is divergent).
+    pub fn get_commits_for_change(&self, change_id: &str) -> Result<Vec<Commit>> {
+        let repo = self.workspace.repo_loader().load_at_head()?;
+        let commit_ids = self.resolve_change_commit_ids(repo.as_ref(), change_id)?;
+        commit_ids
+            .iter()
+            .map(|id| Ok(repo.store().get_commit(id)?))
+            .collect()
+    }
+
+    /// Tree the commit's changes are relative to: its parent's tree, or for merges the
+    /// auto-merged tree of all parents (as `jj diff` shows it).
     pub fn get_parent_tree(&self, commit: &Commit) -> Result<MergedTree> {
         let repo = self.workspace.repo_loader().load_at_head()?;
-        let parents = commit.parents();
-        let parent = parents.into_iter().next().context("Commit has no parent")?;
-        let parent_commit = repo.store().get_commit(parent?.id())?;
-        Ok(parent_commit.tree()?)
+        Ok(commit.parent_tree(repo.as_ref())?)
     }
 
     pub fn get_file_content(&self, commit: &Commit, path: &str) -> Result<Vec<u8>> {
         let repo_path = RepoPath::from_internal_string(path).context("Invalid path")?;
         let tree = commit.tree()?;
         let file_value = tree.path_value(repo_path)?;
-
-        match file_value.into_resolved() {
-            Ok(Some(value)) => {
-                use jj_lib::backend::TreeValue;
-                match value {
-                    TreeValue::File { id, .. } => {
-                        let repo = self.workspace.repo_loader().load_at_head()?;
-                        let mut reader = pollster::block_on(async {
-                            repo.store().read_file(repo_path, &id).await
-                        })?;
-                        let mut content = Vec::new();
-                        pollster::block_on(async { reader.read_to_end(&mut content).await })?;
-                        Ok(content)
-                    }
-                    _ => Ok(Vec::new()),
-                }
-            }
-            _ => Ok(Vec::new()),
-        }
+        pollster::block_on(diff::read_file_value(tree.store(), repo_path, &file_value))
     }
 
     pub fn get_parent_file_content(&self, commit: &Commit, path: &str) -> Result<Vec<u8>> {
         let repo_path = RepoPath::from_internal_string(path).context("Invalid path")?;
         let repo = self.workspace.repo_loader().load_at_head()?;
-        let parents = commit.parents();
-        let parent = parents.into_iter().next().context("Commit has no parent")?;
-        let parent_commit = repo.store().get_commit(parent?.id())?;
-        let parent_tree = parent_commit.tree()?;
+        let parent_tree = commit.parent_tree(repo.as_ref())?;
         let file_value = parent_tree.path_value(repo_path)?;
-
-        match file_value.into_resolved() {
-            Ok(Some(value)) => {
-                use jj_lib::backend::TreeValue;
-                match value {
-                    TreeValue::File { id, .. } => {
-                        let mut reader = pollster::block_on(async {
-                            repo.store().read_file(repo_path, &id).await
-                        })?;
-                        let mut content = Vec::new();
-                        pollster::block_on(async { reader.read_to_end(&mut content).await })?;
-                        Ok(content)
-                    }
-                    _ => Ok(Vec::new()),
-                }
-            }
-            _ => Ok(Vec::new()),
-        }
+        pollster::block_on(diff::read_file_value(repo.store(), repo_path, &file_value))
     }
 
     fn resolve_change_id(&self, repo: &impl Repo, change_id_prefix: &str) -> Result<CommitId> {
@@ -166,6 +199,79 @@ impl JjRepo {
         }
     }
 
+    fn resolve_change_commit_ids(
+        &self,
+        repo: &impl Repo,
+        change_id_prefix: &str,
+    ) -> Result<Vec<CommitId>> {
+        let prefix = HexPrefix::try_from_reverse_hex(change_id_prefix)
+            .context("Invalid change ID prefix format")?;
+
+        let resolution = repo
+            .resolve_change_id_prefix(&prefix)
+            .context("Failed to resolve change ID")?;
+
+        match resolution {
+            PrefixResolution::SingleMatch(commit_ids) => Ok(commit_ids),
+            PrefixResolution::NoMatch => {
+                anyhow::bail!("Change ID not found: {}", change_id_prefix)
+            }
+            PrefixResolution::AmbiguousMatch => {
+                anyhow::bail!("Ambiguous change ID prefix: {}", change_id_prefix)
+            }
+        }
+    }
+
+    fn resolve_commit_id(&self, repo: &impl Repo, commit_id_prefix: &str) -> Result<CommitId> {
+        let prefix =
+            HexPrefix::try_from_hex(commit_id_prefix).context("Invalid commit ID prefix format")?;
+
+        let resolution = repo
+            .index()
+            .resolve_commit_id_prefix(&prefix)
+            .context("Failed to resolve commit ID")?;
+
+        match resolution {
+            PrefixResolution::SingleMatch(commit_id) => Ok(commit_id),
+            PrefixResolution::NoMatch => {
+                anyhow::bail!("Commit ID not found: {}", commit_id_prefix)
+            }
+            PrefixResolution::AmbiguousMatch => {
+                anyhow::bail!("Ambiguous commit ID prefix: {}", commit_id_prefix)
+            }
+        }
+    }
+
+    /// Fail if any of `commit_ids` is immutable, i.e. an ancestor of `immutable_heads()`.
+    /// `ignore_immutable` skips the check, like jj's `--ignore-immutable`.
+    fn check_rewritable(
+        &self,
+        repo: &dyn Repo,
+        commit_ids: &[CommitId],
+        ignore_immutable: bool,
+    ) -> Result<()> {
+        if ignore_immutable {
+            return Ok(());
+        }
+
+        let immutable = RevsetEnvironment::new(self).resolve(repo, IMMUTABLE_REVSET)?;
+        let first_immutable = immutable
+            .intersection(&RevsetExpression::commits(commit_ids.to_vec()))
+            .evaluate(repo)?
+            .iter()
+            .next()
+            .transpose()?;
+
+        if let Some(commit_id) = first_immutable {
+            anyhow::bail!(
+                "Commit {} is immutable",
+                hex::encode(&commit_id.to_bytes()[..6])
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn repo_loader(&self) -> &jj_lib::repo::RepoLoader {
         self.workspace.repo_loader()
     }
@@ -182,6 +288,56 @@ impl JjRepo {
         self.workspace.workspace_root()
     }
 
+    /// Converts user paths relative to the workspace root
+    pub fn path_converter(&self) -> RepoPathUiConverter {
+        let workspace_root = self.workspace_root().to_path_buf();
+        RepoPathUiConverter::Fs {
+            cwd: workspace_root.clone(),
+            base: workspace_root,
+        }
+    }
+
+    /// Parse path arguments like `jj log <paths>` does, relative to the workspace root.
+    /// Bare paths match everything below them; other fileset expressions work too.
+    pub fn parse_filesets(&self, texts: &[String]) -> Result<FilesetExpression> {
+        let path_converter = self.path_converter();
+        let mut diagnostics = FilesetDiagnostics::new();
+        let expressions = texts
+            .iter()
+            .map(|text| {
+                fileset::parse_maybe_bare(&mut diagnostics, text, &path_converter)
+                    .with_context(|| format!("Failed to parse fileset: {}", text))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(FilesetExpression::union_all(expressions))
+    }
+
+    /// Resolve a revset that must name exactly one revision, like `jj diff --from`.
+    pub fn resolve_single_revision(&self, revset_str: &str) -> Result<Commit> {
+        let repo = self.repo_loader().load_at_head()?;
+        let revset_env = RevsetEnvironment::new(self);
+        let revset = revset_env.evaluate(repo.as_ref(), revset_str)?;
+
+        let mut commit_ids = revset.iter();
+        let commit_id = commit_ids.next().with_context(|| {
+            format!("Revset `{}` didn't resolve to any revisions", revset_str)
+        })??;
+        if commit_ids.next().is_some() {
+            anyhow::bail!("Revset `{}` resolved to more than one revision", revset_str);
+        }
+
+        Ok(repo.store().get_commit(&commit_id)?)
+    }
+
+    /// Matcher for a fileset expression such as `glob:"src/**"` or `~Cargo.lock`,
+    /// or everything when None.
+    pub fn fileset_matcher(&self, fileset: Option<&str>) -> Result<Box<dyn Matcher>> {
+        match fileset {
+            Some(text) => Ok(self.parse_filesets(&[text.to_string()])?.to_matcher()),
+            None => Ok(Box::new(EverythingMatcher)),
+        }
+    }
+
     /// Generate change IDs using jj-lib's RNG. Returns reverse-hex encoded IDs.
     pub fn generate_change_ids(&self, count: usize) -> Result<Vec<String>> {
         let repo = self.workspace.repo_loader().load_at_head()?;
@@ -207,26 +363,30 @@ impl JjRepo {
         let mut parent_commits = Vec::new();
         for change_id in parent_change_ids {
             let commit_id = self.resolve_change_id(repo.as_ref(), &change_id)?;
-            let commit = repo.store().get_commit(&commit_id)
+            let commit = repo
+                .store()
+                .get_commit(&commit_id)
                 .map_err(|e| anyhow::anyhow!("Failed to get commit: {}", e))?;
             parent_commits.push(commit);
         }
 
-        // Get the tree from the first parent (empty commit uses parent's tree)
-        let tree_id = parent_commits
-            .first()
-            .context("No parent commits provided")?
-            .tree_id()
-            .clone();
+        if parent_commits.is_empty() {
+            anyhow::bail!("No parent commits provided");
+        }
 
-        // Create new commit with parent commits and their tree (no changes)
+        // Merge the parents' trees (a single parent's tree is used as-is).
+        // Unresolvable conflicts are recorded in the resulting tree, like `jj new A B`.
+        let merged_tree = pollster::block_on(merge_commit_trees(repo.as_ref(), &parent_commits))
+            .context("Failed to merge parent trees")?;
+        let tree_id = merged_tree.id();
+
+        // Create new commit with parent commits and their merged tree (no changes)
         let parent_commit_ids: Vec<_> = parent_commits.iter().map(|c| c.id().clone()).collect();
         let mut commit_builder = tx.repo_mut().new_commit(parent_commit_ids, tree_id);
 
         // Set pre-generated change ID if provided
         if let Some(ref cid) = change_id {
-            let parsed = ChangeId::try_from_reverse_hex(cid)
-                .context("Invalid change ID format")?;
+            let parsed = ChangeId::try_from_reverse_hex(cid).context("Invalid change ID format")?;
             commit_builder = commit_builder.set_change_id(parsed);
         }
 
@@ -246,8 +406,11 @@ impl JjRepo {
         // Get old tree for checkout
         let old_commit = repo
             .store()
-            .get_commit(repo.view().get_wc_commit_id(self.workspace.workspace_name())
-            .context("No working copy commit")?)
+            .get_commit(
+                repo.view()
+                    .get_wc_commit_id(self.workspace.workspace_name())
+                    .context("No working copy commit")?,
+            )
             .map_err(|e| anyhow::anyhow!("Failed to get old commit: {}", e))?;
         let old_tree_id = old_commit.tree_id().clone();
 
@@ -263,14 +426,21 @@ impl JjRepo {
         Ok(actual_change_id)
     }
 
-    pub fn abandon_revision(&mut self, change_id: &str) -> Result<()> {
+    pub fn abandon_revision(&mut self, change_id: &str, ignore_immutable: bool) -> Result<()> {
         let repo = self.workspace.repo_loader().load_at_head()?;
         let mut tx = repo.start_transaction();
 
         // Resolve change ID to commit
         let commit_id = self.resolve_change_id(repo.as_ref(), change_id)?;
-        let commit = repo.store().get_commit(&commit_id)
+        let commit = repo
+            .store()
+            .get_commit(&commit_id)
             .map_err(|e| anyhow::anyhow!("Failed to get commit: {}", e))?;
+        self.check_rewritable(
+            repo.as_ref(),
+            std::slice::from_ref(&commit_id),
+            ignore_immutable,
+        )?;
 
         // Get the current working copy info before changes
         let wc_commit_id = repo
@@ -285,43 +455,37 @@ impl JjRepo {
         // Rebase descendants (this handles moving children to the parent)
         tx.repo_mut().rebase_descendants()?;
 
-        // If we abandoned the working copy, check out the parent
+        // If we abandoned the working copy, edit the parent instead
         if is_abandoning_wc {
-            let parent_id = commit.parent_ids().first().cloned()
+            let parent_id = commit
+                .parent_ids()
+                .first()
+                .cloned()
                 .context("Abandoned commit has no parent")?;
-            let parent_commit = repo.store().get_commit(&parent_id)?;
-
-            // Set the parent as the new working copy
             let workspace_name = self.workspace.workspace_name().to_owned();
             tx.repo_mut()
-                .set_wc_commit(workspace_name, parent_id.clone())
+                .set_wc_commit(workspace_name, parent_id)
                 .context("Failed to set working copy commit")?;
-
-            // Finalize transaction before checkout
-            let new_repo = tx.commit("abandon")?;
-            let operation_id = new_repo.operation().id().clone();
-
-            // Check out the parent commit
-            let old_tree_id = commit.tree_id().clone();
-            self.workspace
-                .check_out(operation_id, Some(&old_tree_id), &parent_commit)
-                .context("Failed to check out parent commit")?;
-        } else {
-            // Finalize transaction
-            tx.commit("abandon")?;
         }
 
-        Ok(())
+        self.finish_transaction(tx, "abandon")
     }
 
-    pub fn edit_revision(&mut self, change_id: String) -> Result<()> {
+    pub fn edit_revision(&mut self, change_id: String, ignore_immutable: bool) -> Result<()> {
         let repo = self.workspace.repo_loader().load_at_head()?;
         let mut tx = repo.start_transaction();
 
         // Resolve change ID to commit
         let commit_id = self.resolve_change_id(repo.as_ref(), &change_id)?;
-        let commit = repo.store().get_commit(&commit_id)
+        let commit = repo
+            .store()
+            .get_commit(&commit_id)
             .map_err(|e| anyhow::anyhow!("Failed to get commit: {}", e))?;
+        self.check_rewritable(
+            repo.as_ref(),
+            std::slice::from_ref(&commit_id),
+            ignore_immutable,
+        )?;
 
         // Set as working copy
         let workspace_name = self.workspace.workspace_name().to_owned();
@@ -332,8 +496,11 @@ impl JjRepo {
         // Get old tree for checkout
         let old_commit = repo
             .store()
-            .get_commit(repo.view().get_wc_commit_id(self.workspace.workspace_name())
-            .context("No working copy commit")?)
+            .get_commit(
+                repo.view()
+                    .get_wc_commit_id(self.workspace.workspace_name())
+                    .context("No working copy commit")?,
+            )
             .map_err(|e| anyhow::anyhow!("Failed to get old commit: {}", e))?;
         let old_tree_id = old_commit.tree_id().clone();
 
@@ -349,6 +516,201 @@ impl JjRepo {
         Ok(())
     }
 
+    /// Resolve a divergent change by abandoning some of its copies and/or giving one
+    /// copy a fresh change ID. Commit IDs (hex prefixes) must be copies of `change_id`.
+    pub fn resolve_divergence(
+        &mut self,
+        change_id: &str,
+        abandon_commit_ids: Vec<String>,
+        renew_commit_id: Option<String>,
+        ignore_immutable: bool,
+    ) -> Result<()> {
+        let repo = self.workspace.repo_loader().load_at_head()?;
+        let copies: Vec<Commit> = self
+            .resolve_change_commit_ids(repo.as_ref(), change_id)?
+            .iter()
+            .map(|id| repo.store().get_commit(id))
+            .collect::<Result<_, _>>()?;
+
+        if copies.len() < 2 {
+            anyhow::bail!("Change is not divergent: {}", change_id);
+        }
+
+        let find_copy = |commit_id: &str| {
+            let mut matches = copies
+                .iter()
+                .filter(|commit| commit.id().hex().starts_with(commit_id));
+            match (matches.next(), matches.next()) {
+                (Some(commit), None) => Ok(commit),
+                (None, _) => Err(anyhow::anyhow!(
+                    "Commit {} is not a copy of {}",
+                    commit_id,
+                    change_id
+                )),
+                (Some(_), Some(_)) => Err(anyhow::anyhow!(
+                    "Commit ID prefix {} is ambiguous",
+                    commit_id
+                )),
+            }
+        };
+        let mut to_abandon = abandon_commit_ids
+            .iter()
+            .map(|id| find_copy(id))
+            .collect::<Result<Vec<_>>>()?;
+        // The same copy may be named twice, e.g. by two prefixes of its id
+        to_abandon.sort_by_key(|commit| commit.id());
+        to_abandon.dedup_by_key(|commit| commit.id());
+        let to_renew = renew_commit_id.as_deref().map(find_copy).transpose()?;
+
+        if to_abandon.len() >= copies.len() {
+            anyhow::bail!("At least one copy of the change must be kept");
+        }
+        if let Some(renew) = to_renew
+            && to_abandon.iter().any(|commit| commit.id() == renew.id())
+        {
+            anyhow::bail!("Cannot both abandon and renew commit {}", renew.id().hex());
+        }
+
+        let rewritten_ids: Vec<CommitId> = to_abandon
+            .iter()
+            .chain(to_renew.iter())
+            .map(|commit| commit.id().clone())
+            .collect();
+        self.check_rewritable(repo.as_ref(), &rewritten_ids, ignore_immutable)?;
+
+        let mut tx = repo.start_transaction();
+
+        for commit in &to_abandon {
+            tx.repo_mut().record_abandoned_commit(commit);
+        }
+
+        if let Some(commit) = to_renew {
+            tx.repo_mut()
+                .rewrite_commit(commit)
+                .generate_new_change_id()
+                .write()
+                .map_err(|e| anyhow::anyhow!("Failed to write commit: {}", e))?;
+        }
+
+        // Rebase descendants; this also moves `@` off abandoned or rewritten copies
+        tx.repo_mut().rebase_descendants()?;
+
+        self.finish_transaction(tx, "resolve divergence")
+    }
+
+    /// Commit the transaction and check out the working-copy commit if the
+    /// transaction moved it (e.g. because it was rewritten or abandoned).
+    fn finish_transaction(&mut self, tx: Transaction, description: &str) -> Result<()> {
+        let workspace_name = self.workspace.workspace_name().to_owned();
+        let old_wc_commit_id = tx
+            .base_repo()
+            .view()
+            .get_wc_commit_id(&workspace_name)
+            .cloned();
+
+        let new_repo = tx.commit(description)?;
+
+        let new_wc_commit_id = new_repo.view().get_wc_commit_id(&workspace_name).cloned();
+        if let Some(new_wc_commit_id) = new_wc_commit_id
+            && Some(&new_wc_commit_id) != old_wc_commit_id.as_ref()
+        {
+            let old_tree_id = match &old_wc_commit_id {
+                Some(id) => Some(new_repo.store().get_commit(id)?.tree_id().clone()),
+                None => None,
+            };
+            let new_wc_commit = new_repo.store().get_commit(&new_wc_commit_id)?;
+            let operation_id = new_repo.operation().id().clone();
+
+            self.workspace
+                .check_out(operation_id, old_tree_id.as_ref(), &new_wc_commit)
+                .context("Failed to check out working copy commit")?;
+        }
+
+        Ok(())
+    }
+
+    /// Move `@` `offset` generations towards children (`Next`) or parents (`Prev`),
+    /// following `jj next` / `jj prev` semantics.
+    ///
+    /// Without `edit`, a new empty revision is created on top of the target, counting
+    /// from `@-`. With `edit` (implied when `@` has children), the target is edited
+    /// directly, counting from `@`. Ambiguous targets are returned as candidates.
+    pub fn move_relative(
+        &mut self,
+        direction: Direction,
+        offset: u64,
+        edit: bool,
+        ignore_immutable: bool,
+    ) -> Result<MovementResult> {
+        let repo = self.workspace.repo_loader().load_at_head()?;
+        let wc_commit_id = repo
+            .view()
+            .get_wc_commit_id(self.workspace.workspace_name())
+            .context("No working copy commit")?
+            .clone();
+
+        let wc_expression = RevsetExpression::commits(vec![wc_commit_id]);
+        let wc_has_children = !wc_expression.children().evaluate(repo.as_ref())?.is_empty();
+        let edit = edit || wc_has_children;
+
+        let start = if edit {
+            wc_expression.clone()
+        } else {
+            wc_expression.parents()
+        };
+        let mut target_expression = match direction {
+            // `@` itself is a child of `@-`, so don't count it as a destination
+            Direction::Next => start.descendants_at(offset).minus(&wc_expression),
+            Direction::Prev => start.ancestors_at(offset),
+        };
+        if edit {
+            // The root commit can be built upon but not edited
+            target_expression = target_expression.minus(&RevsetExpression::root());
+        }
+
+        let target_ids: Vec<CommitId> = target_expression
+            .evaluate(repo.as_ref())?
+            .iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let target_change_id = match target_ids.as_slice() {
+            [] => {
+                let relation = match direction {
+                    Direction::Next => "descendant",
+                    Direction::Prev => "ancestor",
+                };
+                anyhow::bail!("No {} found {} revision(s) away", relation, offset)
+            }
+            [target_id] => repo
+                .store()
+                .get_commit(target_id)?
+                .change_id()
+                .reverse_hex(),
+            _ => {
+                let candidates = target_ids
+                    .iter()
+                    .map(|id| Ok(repo.store().get_commit(id)?.change_id().reverse_hex()))
+                    .collect::<Result<Vec<_>>>()?;
+                return Ok(MovementResult {
+                    change_id: None,
+                    candidates,
+                });
+            }
+        };
+
+        let change_id = if edit {
+            self.edit_revision(target_change_id.clone(), ignore_immutable)?;
+            target_change_id
+        } else {
+            self.new_revision(vec![target_change_id], None)?
+        };
+
+        Ok(MovementResult {
+            change_id: Some(change_id),
+            candidates: Vec::new(),
+        })
+    }
+
     /// Walk the operation log to find when each commit was last the working copy.
     /// Returns a map of commit_id (hex) -> timestamp_millis.
     /// This is used to determine "recency" for branch ordering.
@@ -1,18 +1,36 @@
 use anyhow::{Context, Result};
-use jj_lib::backend::CommitId;
-use jj_lib::git;
+use jj_lib::backend::{ChangeId, CommitId};
+use jj_lib::commit::Commit;
 use jj_lib::graph::{GraphEdge, GraphEdgeType};
+use jj_lib::matchers::EverythingMatcher;
 use jj_lib::object_id::ObjectId;
+use jj_lib::op_store::OperationId;
 use jj_lib::repo::Repo;
-use jj_lib::repo_path::RepoPathUiConverter;
 use jj_lib::revset::{
-    parse, RevsetAliasesMap, RevsetDiagnostics, RevsetExpression, RevsetExtensions,
-    RevsetParseContext, RevsetWorkspaceContext, SymbolResolver, SymbolResolverExtension,
+    RevsetDiagnostics, RevsetExpression, RevsetFilterPredicate, SymbolResolver,
+    SymbolResolverExtension, parse,
 };
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{LazyLock, Mutex};
 
+use super::diff::{self, DiffOptions, DiffStats};
 use super::jj::JjRepo;
+use super::revset::{
+    IMMUTABLE_REVSET, RevsetCompletions, RevsetEnvironment, RevsetError, TrunkSettings,
+};
+use crate::storage::RevsetPreset;
+
+const ACTIVE_REVSET: &str = "present(@) | ancestors(immutable_heads().., 2) | present(trunk())";
+
+/// Diff stats of commits already shown in the log, per diff options. Commits never
+/// change, so entries don't go stale and each commit is diffed once rather than on
+/// every refresh.
+static STATS_CACHE: LazyLock<Mutex<HashMap<(CommitId, DiffOptions), DiffStats>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Entries kept in `STATS_CACHE` before it is cleared
+const STATS_CACHE_LIMIT: usize = 10_000;
 
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct ParentEdge {
@@ -25,10 +43,26 @@ pub struct Revision {
     pub commit_id: String,
     pub change_id: String,
     pub change_id_short: String,
+    /// Full-length hex commit id
+    pub commit_id_full: String,
+    /// Full-length change id in jj's reverse hex
+    pub change_id_full: String,
     pub parent_ids: Vec<String>,
     pub parent_edges: Vec<ParentEdge>,
     pub description: String,
     pub author: String,
+    pub author_email: String,
+    /// Milliseconds since the epoch
+    pub author_timestamp: i64,
+    /// Author's UTC offset in minutes
+    pub author_tz_offset: i32,
+    pub committer_name: String,
+    pub committer_email: String,
+    /// Milliseconds since the epoch
+    pub committer_timestamp: i64,
+    /// Committer's UTC offset in minutes
+    pub committer_tz_offset: i32,
+    /// Relative committer time in English, e.g. "3 days ago"
     pub timestamp: String,
     pub is_working_copy: bool,
     pub is_immutable: bool,
@@ -36,12 +70,76 @@ pub struct Revision {
     pub is_trunk: bool,
     pub is_divergent: bool,
     pub divergent_index: Option<usize>,
+    pub is_empty: bool,
+    pub has_conflict: bool,
+    /// Not visible at the loaded operation (only reachable through e.g. a commit id revset)
+    pub is_hidden: bool,
+    pub has_description: bool,
+    /// Size of the change relative to its (merged) parents
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
     pub bookmarks: Vec<String>,
 }
 
-pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: Option<&str>) -> Result<Vec<Revision>> {
-    let jj_repo = JjRepo::open(repo_path)?;
-    let repo = jj_repo.repo_loader().load_at_head()?;
+/// One page of the log
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LogPage {
+    pub revisions: Vec<Revision>,
+    /// Opaque token to pass back for the next page, None once the log is exhausted
+    pub next_cursor: Option<String>,
+}
+
+/// Which revisions the log shows
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogFilter<'a> {
+    /// Explicit revset; takes precedence over `preset`
+    pub revset: Option<&'a str>,
+    pub preset: Option<&'a str>,
+    /// The project's own presets, which shadow the builtin ones
+    pub custom_presets: &'a [RevsetPreset],
+    /// Only show revisions touching these paths or filesets (like `jj log <paths>`)
+    pub paths: &'a [String],
+}
+
+pub fn fetch_log(
+    repo_path: &Path,
+    limit: usize,
+    filter: &LogFilter,
+    trunk: &TrunkSettings,
+    options: &DiffOptions,
+) -> Result<Vec<Revision>> {
+    let page = fetch_log_page(repo_path, limit, None, filter, trunk, options)?;
+    Ok(page.revisions)
+}
+
+/// Fetch `limit` revisions starting at `cursor` (or the top of the log when None).
+///
+/// The cursor pins the operation the first page was loaded at, so later pages stay
+/// consistent with earlier ones even if the repo changes in between. It also holds
+/// the roots of everything shown so far: the log is in index order, where every
+/// commit comes after its descendants, so the rest of the log is the revset minus
+/// the descendants of those roots and earlier pages are never walked again.
+pub fn fetch_log_page(
+    repo_path: &Path,
+    limit: usize,
+    cursor: Option<&str>,
+    filter: &LogFilter,
+    trunk: &TrunkSettings,
+    options: &DiffOptions,
+) -> Result<LogPage> {
+    let jj_repo = JjRepo::open_with_trunk(repo_path, trunk)?;
+    let repo_loader = jj_repo.repo_loader();
+    let (repo, shown_roots) = match cursor {
+        Some(cursor) => {
+            let (op_id, shown_roots) = decode_cursor(cursor)?;
+            let op = repo_loader
+                .load_operation(&op_id)
+                .context("Log cursor refers to an unknown operation")?;
+            (repo_loader.load_at(&op)?, shown_roots)
+        }
+        None => (repo_loader.load_at_head()?, Vec::new()),
+    };
     let user_email = jj_repo.user_settings().user_email();
 
     let wc_id = repo
@@ -51,89 +149,79 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
         .next()
         .context("No working copy")?;
 
+    // The user's `revsets.log` takes precedence over the default preset, like `jj log`
+    let configured_log_revset = jj_repo.user_settings().get_string("revsets.log").ok();
+
     // Determine which revset to use
-    let revset_str = if let Some(custom_revset) = revset {
+    let revset_str = if let Some(custom_revset) = filter.revset {
         custom_revset
-    } else if let Some(preset_name) = preset {
-        match preset_name {
-            "my_work" => "mine() | present(@)",
-            "active" => "present(@) | ancestors(immutable_heads().., 2) | present(trunk())",
-            "full_history" => "ancestors(visible_heads())",
-            _ => "present(@) | ancestors(immutable_heads().., 2) | present(trunk())", // default to active
-        }
+    } else if let Some(preset_name) = filter.preset {
+        preset_revset(preset_name, filter.custom_presets)
     } else {
-        // Default to "active" preset
-        "present(@) | ancestors(immutable_heads().., 2) | present(trunk())"
-    };
-
-    // Set up aliases needed for presets
-    let mut aliases_map = RevsetAliasesMap::new();
-
-    // trunk() - jj-cli style using remote_bookmarks with fallback to root
-    aliases_map.insert(
-        "trunk()",
-        r#"latest(
-            remote_bookmarks(exact:"main", exact:"origin") |
-            remote_bookmarks(exact:"master", exact:"origin") |
-            remote_bookmarks(exact:"trunk", exact:"origin") |
-            root()
-        )"#,
-    ).ok();
-
-    // builtin_immutable_heads() - trunk + tags + untracked remote bookmarks
-    aliases_map.insert("builtin_immutable_heads()", "present(trunk()) | tags() | untracked_remote_bookmarks()").ok();
-
-    // immutable_heads() - defaults to builtin
-    aliases_map.insert("immutable_heads()", "builtin_immutable_heads()").ok();
-
-    // mine() - commits authored by current user
-    let mine_revset = format!(r#"author_email(exact-i:"{}")"#, user_email);
-    aliases_map.insert("mine()", &mine_revset).ok();
-
-    // Create workspace context for @ resolution
-    let path_converter = RepoPathUiConverter::Fs {
-        cwd: repo_path.to_path_buf(),
-        base: repo_path.to_path_buf(),
-    };
-    let workspace_name = jj_repo.workspace_name();
-    let workspace_ctx = RevsetWorkspaceContext {
-        path_converter: &path_converter,
-        workspace_name,
+        // Default to `revsets.log`, falling back to the "active" preset
+        configured_log_revset.as_deref().unwrap_or(ACTIVE_REVSET)
     };
 
-    let context = RevsetParseContext {
-        aliases_map: &aliases_map,
-        local_variables: HashMap::new(),
-        user_email: jj_repo.user_settings().user_email(),
-        date_pattern_context: chrono::Utc::now().fixed_offset().into(),
-        default_ignored_remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
-        extensions: &RevsetExtensions::default(),
-        workspace: Some(workspace_ctx),
-    };
+    let revset_env = RevsetEnvironment::new(&jj_repo);
+    let context = revset_env.parse_context();
 
     let mut diagnostics = RevsetDiagnostics::new();
-    let expression = parse(&mut diagnostics, revset_str, &context)
-        .context("Failed to parse revset")?;
+    let mut expression =
+        parse(&mut diagnostics, revset_str, &context).context("Failed to parse revset")?;
+
+    if !filter.paths.is_empty() {
+        let fileset = jj_repo.parse_filesets(filter.paths)?;
+        expression = expression.intersection(&RevsetExpression::filter(
+            RevsetFilterPredicate::File(fileset),
+        ));
+    }
 
-    let symbol_resolver = SymbolResolver::new(repo.as_ref(), &([] as [&Box<dyn SymbolResolverExtension>; 0]));
-    let resolved = expression.resolve_user_expression(repo.as_ref(), &symbol_resolver)
+    // Everything from earlier pages descends from their roots
+    if !shown_roots.is_empty() {
+        expression =
+            expression.minus(&RevsetExpression::commits(shown_roots.clone()).descendants());
+    }
+
+    let symbol_resolver = SymbolResolver::new(
+        repo.as_ref(),
+        &([] as [&Box<dyn SymbolResolverExtension>; 0]),
+    );
+    let resolved = expression
+        .resolve_user_expression(repo.as_ref(), &symbol_resolver)
         .context("Failed to resolve revset")?;
 
-    let revset_expression = resolved.evaluate(repo.as_ref())
+    let revset_expression = resolved
+        .evaluate(repo.as_ref())
         .context("Failed to evaluate revset")?;
 
     // Use iter_graph() to get commits with edge information
-    let graph_nodes: Vec<(CommitId, Vec<GraphEdge<CommitId>>)> = revset_expression
+    let mut graph_iter = revset_expression
         .iter_graph()
+        .map(|result| result.map_err(|e| anyhow::anyhow!("Graph iteration error: {}", e)));
+    let graph_nodes: Vec<(CommitId, Vec<GraphEdge<CommitId>>)> = graph_iter
+        .by_ref()
         .take(limit)
-        .map(|result| {
-            result.map_err(|e| anyhow::anyhow!("Graph iteration error: {}", e))
-        })
         .collect::<Result<Vec<_>>>()?;
+    let next_cursor = match graph_iter.next() {
+        Some(next) => {
+            next?;
+            let shown = shown_roots
+                .into_iter()
+                .chain(graph_nodes.iter().map(|(commit_id, _)| commit_id.clone()))
+                .collect();
+            let roots = RevsetExpression::commits(shown)
+                .roots()
+                .evaluate(repo.as_ref())?
+                .iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(encode_cursor(repo.op_id(), &roots))
+        }
+        None => None,
+    };
 
-    let immutable_expression = RevsetExpression::root();
-    let immutable_revset = immutable_expression.evaluate(repo.as_ref())?;
-    let immutable_ids: Vec<CommitId> = immutable_revset.iter().collect::<Result<Vec<_>, _>>()?;
+    // Ancestors of immutable_heads() (respecting the user's override) can't be rewritten
+    let immutable_revset = revset_env.evaluate(repo.as_ref(), IMMUTABLE_REVSET)?;
+    let is_immutable_commit = immutable_revset.containing_fn();
 
     // Evaluate ::trunk() to identify trunk ancestors
     let trunk_ancestor_ids: std::collections::HashSet<CommitId> = {
@@ -141,12 +229,10 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
         match parse(&mut trunk_diagnostics, "::trunk()", &context) {
             Ok(trunk_expr) => {
                 match trunk_expr.resolve_user_expression(repo.as_ref(), &symbol_resolver) {
-                    Ok(resolved) => {
-                        match resolved.evaluate(repo.as_ref()) {
-                            Ok(revset) => revset.iter().filter_map(|r| r.ok()).collect(),
-                            Err(_) => std::collections::HashSet::new(),
-                        }
-                    }
+                    Ok(resolved) => match resolved.evaluate(repo.as_ref()) {
+                        Ok(revset) => revset.iter().filter_map(|r| r.ok()).collect(),
+                        Err(_) => std::collections::HashSet::new(),
+                    },
                     Err(_) => std::collections::HashSet::new(),
                 }
             }
@@ -154,19 +240,9 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
         }
     };
 
-    // First pass: count occurrences of each change_id to detect divergence
-    let mut change_id_counts: HashMap<String, usize> = HashMap::new();
-    let mut commit_change_ids: Vec<String> = Vec::new();
-
-    for (commit_id, _) in &graph_nodes {
-        let commit = repo.store().get_commit(commit_id)?;
-        let change_id_str = format_change_id(commit.change_id());
-        *change_id_counts.entry(change_id_str.clone()).or_insert(0) += 1;
-        commit_change_ids.push(change_id_str);
-    }
-
-    // Track which index we're at for each divergent change_id
-    let mut divergent_indices: HashMap<String, usize> = HashMap::new();
+    // Visible copies of each change in log order. Looked up repo-wide rather than
+    // within the page so /N suffixes don't shift between pages.
+    let mut change_copies: HashMap<ChangeId, Vec<CommitId>> = HashMap::new();
 
     let mut revisions = Vec::new();
 
@@ -174,7 +250,7 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
         let commit = repo.store().get_commit(&commit_id)?;
         let change_id = commit.change_id();
         let is_working_copy = wc_id == &commit_id;
-        let is_immutable = immutable_ids.contains(&commit_id);
+        let is_immutable = is_immutable_commit(&commit_id)?;
 
         let description = commit.description().to_string();
 
@@ -189,6 +265,13 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
 
         let bookmarks = get_bookmarks_for_commit(repo.as_ref(), &commit_id);
 
+        let is_empty = commit.is_empty(repo.as_ref())?;
+        let stats = if is_empty {
+            DiffStats::default()
+        } else {
+            commit_stats(repo.as_ref(), &commit, options)?
+        };
+
         // Keep parent_ids for backward compatibility
         let parent_ids: Vec<String> = commit
             .parent_ids()
@@ -215,14 +298,15 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
             .shortest_unique_change_id_prefix_len(change_id)
             .unwrap_or(full_change_id.len());
 
-        // Check if this change_id is divergent (appears multiple times)
-        let count = change_id_counts.get(&full_change_id).copied().unwrap_or(1);
-        let is_divergent = count > 1;
+        // Check if this change_id is divergent (has several visible commits)
+        if !change_copies.contains_key(change_id) {
+            let copies = visible_copies(repo.as_ref(), change_id)?;
+            change_copies.insert(change_id.clone(), copies);
+        }
+        let copies = &change_copies[change_id];
+        let is_divergent = copies.len() > 1;
         let divergent_index = if is_divergent {
-            let idx = divergent_indices.entry(full_change_id.clone()).or_insert(0);
-            let current_idx = *idx;
-            *idx += 1;
-            Some(current_idx)
+            copies.iter().position(|id| id == &commit_id)
         } else {
             None
         };
@@ -240,10 +324,19 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
             commit_id: hex::encode(&commit_id.to_bytes()[..6]),
             change_id: full_change_id,
             change_id_short,
+            commit_id_full: commit_id.hex(),
+            change_id_full: change_id.reverse_hex(),
             parent_ids,
             parent_edges,
             description,
             author: author_name,
+            author_email,
+            author_timestamp: author.timestamp.timestamp.0,
+            author_tz_offset: author.timestamp.tz_offset,
+            committer_name: committer.name.clone(),
+            committer_email: committer.email.clone(),
+            committer_timestamp: committer.timestamp.timestamp.0,
+            committer_tz_offset: committer.timestamp.tz_offset,
             timestamp,
             is_working_copy,
             is_immutable,
@@ -251,14 +344,93 @@ pub fn fetch_log(repo_path: &Path, limit: usize, revset: Option<&str>, preset: O
             is_trunk,
             is_divergent,
             divergent_index,
+            is_empty,
+            has_conflict: commit.has_conflict(),
+            is_hidden: commit.is_hidden(repo.as_ref())?,
+            has_description: !commit.description().trim().is_empty(),
+            files_changed: stats.files_changed,
+            insertions: stats.insertions,
+            deletions: stats.deletions,
             bookmarks,
         });
     }
 
-    Ok(revisions)
+    Ok(LogPage {
+        revisions,
+        next_cursor,
+    })
 }
 
-fn format_change_id(change_id: &jj_lib::backend::ChangeId) -> String {
+/// Files and lines a commit changes relative to its parents, from `STATS_CACHE` when
+/// the commit was seen before.
+fn commit_stats(repo: &dyn Repo, commit: &Commit, options: &DiffOptions) -> Result<DiffStats> {
+    let key = (commit.id().clone(), options.clone());
+    if let Some(stats) = STATS_CACHE.lock().unwrap().get(&key) {
+        return Ok(*stats);
+    }
+
+    let parent_tree = commit.parent_tree(repo)?;
+    let stats =
+        diff::compute_tree_stats(&parent_tree, &commit.tree()?, &EverythingMatcher, options)?;
+
+    let mut cache = STATS_CACHE.lock().unwrap();
+    if cache.len() >= STATS_CACHE_LIMIT {
+        cache.clear();
+    }
+    cache.insert(key, stats);
+    Ok(stats)
+}
+
+/// Visible commits of a change, in log order
+fn visible_copies(repo: &dyn Repo, change_id: &ChangeId) -> Result<Vec<CommitId>> {
+    let Some(commit_ids) = repo.resolve_change_id(change_id)? else {
+        return Ok(Vec::new());
+    };
+    if commit_ids.len() < 2 {
+        return Ok(commit_ids);
+    }
+    RevsetExpression::commits(commit_ids)
+        .evaluate(repo)?
+        .iter()
+        .map(|id| Ok(id?))
+        .collect()
+}
+
+/// `<operation>:<root>,<root>,...` with every id in hex
+fn encode_cursor(op_id: &OperationId, shown_roots: &[CommitId]) -> String {
+    let roots: Vec<String> = shown_roots.iter().map(|id| id.hex()).collect();
+    format!("{}:{}", op_id.hex(), roots.join(","))
+}
+
+fn decode_cursor(cursor: &str) -> Result<(OperationId, Vec<CommitId>)> {
+    let (op_hex, roots) = cursor.split_once(':').context("Invalid log cursor")?;
+    let op_id = OperationId::try_from_hex(op_hex).context("Invalid log cursor")?;
+    let shown_roots = roots
+        .split(',')
+        .filter(|_| !roots.is_empty())
+        .map(|root| {
+            CommitId::try_from_hex(root)
+                .filter(|_| !root.is_empty())
+                .context("Invalid log cursor")
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((op_id, shown_roots))
+}
+
+/// Revset for a preset name. The project's own presets shadow the builtin ones,
+/// and unknown names fall back to "active".
+fn preset_revset<'a>(name: &str, custom_presets: &'a [RevsetPreset]) -> &'a str {
+    if let Some(custom) = custom_presets.iter().find(|p| p.name == name) {
+        return &custom.revset;
+    }
+    match name {
+        "my_work" => "mine() | present(@)",
+        "full_history" => "ancestors(visible_heads())",
+        _ => ACTIVE_REVSET,
+    }
+}
+
+pub fn format_change_id(change_id: &jj_lib::backend::ChangeId) -> String {
     let bytes = change_id.to_bytes();
     let mut result = String::with_capacity(12);
     for &byte in &bytes[..6] {
@@ -322,88 +494,38 @@ fn get_bookmarks_for_commit(repo: &dyn Repo, commit_id: &CommitId) -> Vec<String
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct RevsetResult {
     pub change_ids: Vec<String>,
-    pub error: Option<String>,
+    pub error: Option<RevsetError>,
+    /// Parser warnings, e.g. deprecated syntax. Reported even when resolution fails.
+    pub warnings: Vec<RevsetError>,
 }
 
 /// Resolve a revset expression and return matching change IDs
-pub fn resolve_revset(repo_path: &Path, revset_str: &str) -> Result<RevsetResult> {
-    let jj_repo = JjRepo::open(repo_path)?;
+pub fn resolve_revset(
+    repo_path: &Path,
+    revset_str: &str,
+    trunk: &TrunkSettings,
+) -> Result<RevsetResult> {
+    let jj_repo = JjRepo::open_with_trunk(repo_path, trunk)?;
     let repo = jj_repo.repo_loader().load_at_head()?;
 
-    // Set up aliases (same as fetch_log)
-    let mut aliases_map = RevsetAliasesMap::new();
-    let user_email = jj_repo.user_settings().user_email();
+    let revset_env = RevsetEnvironment::new(&jj_repo);
+    let (resolved, warnings) = revset_env.resolve_with_diagnostics(repo.as_ref(), revset_str);
 
-    aliases_map.insert(
-        "trunk()",
-        r#"latest(
-            remote_bookmarks(exact:"main", exact:"origin") |
-            remote_bookmarks(exact:"master", exact:"origin") |
-            remote_bookmarks(exact:"trunk", exact:"origin") |
-            root()
-        )"#,
-    ).ok();
-
-    aliases_map.insert("builtin_immutable_heads()", "present(trunk()) | tags() | untracked_remote_bookmarks()").ok();
-    aliases_map.insert("immutable_heads()", "builtin_immutable_heads()").ok();
-
-    let mine_revset = format!(r#"author_email(exact-i:"{}")"#, user_email);
-    aliases_map.insert("mine()", &mine_revset).ok();
-
-    let path_converter = RepoPathUiConverter::Fs {
-        cwd: repo_path.to_path_buf(),
-        base: repo_path.to_path_buf(),
-    };
-    let workspace_name = jj_repo.workspace_name();
-    let workspace_ctx = RevsetWorkspaceContext {
-        path_converter: &path_converter,
-        workspace_name,
+    let failed = |error: RevsetError, warnings: Vec<RevsetError>| RevsetResult {
+        change_ids: vec![],
+        error: Some(error),
+        warnings,
     };
 
-    let context = RevsetParseContext {
-        aliases_map: &aliases_map,
-        local_variables: HashMap::new(),
-        user_email: jj_repo.user_settings().user_email(),
-        date_pattern_context: chrono::Utc::now().fixed_offset().into(),
-        default_ignored_remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
-        extensions: &RevsetExtensions::default(),
-        workspace: Some(workspace_ctx),
-    };
-
-    let mut diagnostics = RevsetDiagnostics::new();
-    
-    // Parse the revset expression
-    let expression = match parse(&mut diagnostics, revset_str, &context) {
-        Ok(expr) => expr,
-        Err(e) => {
-            return Ok(RevsetResult {
-                change_ids: vec![],
-                error: Some(format!("Parse error: {}", e)),
-            });
-        }
-    };
-
-    // Resolve symbols
-    let symbol_resolver = SymbolResolver::new(repo.as_ref(), &([] as [&Box<dyn SymbolResolverExtension>; 0]));
-    let resolved = match expression.resolve_user_expression(repo.as_ref(), &symbol_resolver) {
-        Ok(r) => r,
-        Err(e) => {
-            return Ok(RevsetResult {
-                change_ids: vec![],
-                error: Some(format!("Resolve error: {}", e)),
-            });
-        }
+    let resolved = match resolved {
+        Ok(resolved) => resolved,
+        Err(error) => return Ok(failed(error, warnings)),
     };
 
     // Evaluate the revset
     let revset = match resolved.evaluate(repo.as_ref()) {
         Ok(r) => r,
-        Err(e) => {
-            return Ok(RevsetResult {
-                change_ids: vec![],
-                error: Some(format!("Evaluation error: {}", e)),
-            });
-        }
+        Err(e) => return Ok(failed(RevsetError::from_evaluation_error(&e), warnings)),
     };
 
     // Collect matching change IDs
@@ -414,17 +536,54 @@ pub fn resolve_revset(repo_path: &Path, revset_str: &str) -> Result<RevsetResult
                 let commit = repo.store().get_commit(&commit_id)?;
                 change_ids.push(format_change_id(commit.change_id()));
             }
-            Err(e) => {
-                return Ok(RevsetResult {
-                    change_ids: vec![],
-                    error: Some(format!("Iteration error: {}", e)),
-                });
-            }
+            Err(e) => return Ok(failed(RevsetError::from_evaluation_error(&e), warnings)),
         }
     }
 
     Ok(RevsetResult {
         change_ids,
         error: None,
+        warnings,
     })
 }
+
+/// Suggest completions for a partially typed revset
+pub fn complete_revset(
+    repo_path: &Path,
+    input: &str,
+    trunk: &TrunkSettings,
+) -> Result<RevsetCompletions> {
+    let jj_repo = JjRepo::open_with_trunk(repo_path, trunk)?;
+    let repo = jj_repo.repo_loader().load_at_head()?;
+
+    let revset_env = RevsetEnvironment::new(&jj_repo);
+    Ok(revset_env.complete(repo.as_ref(), input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        let op_id = OperationId::new(vec![0xab; 64]);
+        let roots = vec![CommitId::new(vec![0x12; 20]), CommitId::new(vec![0x34; 20])];
+        let cursor = encode_cursor(&op_id, &roots);
+        let (decoded_op_id, decoded_roots) = decode_cursor(&cursor).unwrap();
+        assert_eq!(decoded_op_id, op_id);
+        assert_eq!(decoded_roots, roots);
+
+        // Nothing shown yet, e.g. after an empty page
+        let (_, decoded_roots) = decode_cursor(&encode_cursor(&op_id, &[])).unwrap();
+        assert!(decoded_roots.is_empty());
+    }
+
+    #[test]
+    fn malformed_cursor_is_rejected() {
+        assert!(decode_cursor("").is_err());
+        assert!(decode_cursor("abcd").is_err());
+        assert!(decode_cursor("not-hex:10").is_err());
+        assert!(decode_cursor("abcd:ten").is_err());
+        assert!(decode_cursor("abcd:1234,").is_err());
+    }
+}
@@ -1,10 +1,19 @@
 pub mod diff;
+pub mod divergence;
+pub mod evolog;
 pub mod jj;
 pub mod log;
+pub mod revset;
 pub mod status;
 
 use std::path::PathBuf;
 
+/// Convert a byte offset into `text` to UTF-16 code units, the unit JS strings are
+/// indexed in, so offsets sent to the frontend can be used with `slice` directly.
+pub fn utf16_offset(text: &str, byte_offset: usize) -> usize {
+    text[..byte_offset].encode_utf16().count()
+}
+
 pub fn find_jj_repo(start_path: &std::path::Path) -> Option<PathBuf> {
     let mut current = start_path.to_path_buf();
 
@@ -0,0 +1,680 @@
+use anyhow::{Context, Result};
+use jj_lib::git;
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPathUiConverter;
+use jj_lib::revset::{
+    ResolvedRevsetExpression, Revset, RevsetAliasesMap, RevsetDiagnostics, RevsetEvaluationError,
+    RevsetExtensions, RevsetParseContext, RevsetParseError, RevsetParseErrorKind,
+    RevsetResolutionError, RevsetWorkspaceContext, SymbolResolver, SymbolResolverExtension, parse,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use super::jj::JjRepo;
+use super::utf16_offset;
+
+/// Every commit that must not be rewritten (same definition as jj-cli)
+pub const IMMUTABLE_REVSET: &str = "::(immutable_heads() | root())";
+
+/// Builtin revset functions. jj-lib doesn't expose its function table, so this
+/// mirrors the one in jj-lib 0.35 for completion.
+const BUILTIN_FUNCTIONS: &[&str] = &[
+    "parents",
+    "children",
+    "ancestors",
+    "descendants",
+    "first_parent",
+    "first_ancestors",
+    "connected",
+    "reachable",
+    "none",
+    "all",
+    "working_copies",
+    "heads",
+    "roots",
+    "visible_heads",
+    "root",
+    "change_id",
+    "commit_id",
+    "bookmarks",
+    "remote_bookmarks",
+    "tags",
+    "git_refs",
+    "git_head",
+    "latest",
+    "fork_point",
+    "bisect",
+    "exactly",
+    "merges",
+    "description",
+    "subject",
+    "author",
+    "author_name",
+    "author_email",
+    "author_date",
+    "signed",
+    "mine",
+    "committer",
+    "committer_name",
+    "committer_email",
+    "committer_date",
+    "empty",
+    "files",
+    "diff_contains",
+    "conflicts",
+    "present",
+    "at_operation",
+    "coalesce",
+];
+
+/// Byte range into the revset text. The UTF-16 offsets are the same range as JS
+/// string indices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct RevsetSpan {
+    pub start: usize,
+    pub end: usize,
+    pub start_utf16: usize,
+    pub end_utf16: usize,
+}
+
+impl RevsetSpan {
+    fn new(input: &str, start: usize, end: usize) -> Self {
+        Self {
+            start,
+            end,
+            start_utf16: utf16_offset(input, start),
+            end_utf16: utf16_offset(input, end),
+        }
+    }
+}
+
+/// A revset error or warning, structured so the UI can underline it
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RevsetError {
+    /// "parse", "resolve" or "evaluate"
+    pub kind: String,
+    pub message: String,
+    /// Location in the input. Resolution and evaluation errors have none.
+    pub span: Option<RevsetSpan>,
+    pub hints: Vec<String>,
+}
+
+impl RevsetError {
+    pub fn from_parse_error(err: &RevsetParseError, input: &str) -> Self {
+        let rendered = err.to_string();
+        let message = match err.kind() {
+            // The kind only says "Syntax error"; pest's note says what was expected
+            RevsetParseErrorKind::SyntaxError => match rendered_note(&rendered) {
+                Some(note) => format!("Syntax error: {}", note),
+                None => "Syntax error".to_string(),
+            },
+            kind => kind.to_string(),
+        };
+
+        let mut hints = parse_error_hints(err.kind());
+        // Errors inside alias definitions point at the alias call in `input`;
+        // the underlying errors come along as hints.
+        for origin in std::iter::successors(err.origin(), |e| e.origin()) {
+            let origin_rendered = origin.to_string();
+            match origin.kind() {
+                RevsetParseErrorKind::SyntaxError => hints.push(format!(
+                    "Syntax error in alias definition: {}",
+                    rendered_note(&origin_rendered).unwrap_or_default()
+                )),
+                kind => hints.push(kind.to_string()),
+            }
+            hints.extend(parse_error_hints(origin.kind()));
+        }
+
+        Self {
+            kind: "parse".to_string(),
+            message,
+            span: rendered_span(&rendered, input),
+            hints,
+        }
+    }
+
+    pub fn from_resolution_error(err: &RevsetResolutionError) -> Self {
+        let hints = match err {
+            RevsetResolutionError::NoSuchRevision { candidates, .. } => {
+                similarity_hint(candidates).into_iter().collect()
+            }
+            RevsetResolutionError::DivergentChangeId { .. } => {
+                vec!["Use a commit ID to select a single revision".to_string()]
+            }
+            RevsetResolutionError::ConflictedRef { .. } => {
+                vec!["Use a commit ID or resolve the conflicted ref first".to_string()]
+            }
+            RevsetResolutionError::AmbiguousCommitIdPrefix(_)
+            | RevsetResolutionError::AmbiguousChangeIdPrefix(_) => {
+                vec!["Use a longer prefix".to_string()]
+            }
+            _ => Vec::new(),
+        };
+
+        Self {
+            kind: "resolve".to_string(),
+            message: err.to_string(),
+            span: None,
+            hints,
+        }
+    }
+
+    pub fn from_evaluation_error(err: &RevsetEvaluationError) -> Self {
+        Self {
+            kind: "evaluate".to_string(),
+            message: err.to_string(),
+            span: None,
+            hints: Vec::new(),
+        }
+    }
+}
+
+/// One completion candidate for a partially typed revset
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RevsetCompletion {
+    /// Text shown in the list, e.g. `ancestors()` or `main@origin`
+    pub label: String,
+    /// Text that replaces the partial word
+    pub insert_text: String,
+    /// "function", "alias", "bookmark", "tag" or "remote"
+    pub kind: String,
+}
+
+/// Completions for the word ending at the end of the input
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RevsetCompletions {
+    /// Byte offset where the partial word starts
+    pub start: usize,
+    /// The same offset in UTF-16 code units, like JS string indices
+    pub start_utf16: usize,
+    pub items: Vec<RevsetCompletion>,
+}
+
+/// Per-project choice of the bookmark that `trunk()` resolves to
+#[derive(Clone, Debug, Default)]
+pub struct TrunkSettings {
+    pub bookmark: Option<String>,
+    pub remote: Option<String>,
+}
+
+impl TrunkSettings {
+    /// `trunk()` definition for these settings, or None to defer to jj config and
+    /// the main/master/trunk heuristic. The remote defaults to `origin`.
+    pub fn alias_definition(&self) -> Option<String> {
+        if self.bookmark.is_none() && self.remote.is_none() {
+            return None;
+        }
+
+        let remote = self.remote.as_deref().unwrap_or("origin");
+        let bookmarks = match &self.bookmark {
+            Some(bookmark) => vec![bookmark.as_str()],
+            None => vec!["main", "master", "trunk"],
+        };
+        let candidates: Vec<String> = bookmarks
+            .iter()
+            .map(|bookmark| format!("remote_bookmarks(exact:{:?}, exact:{:?})", bookmark, remote))
+            .collect();
+
+        Some(format!("latest({} | root())", candidates.join(" | ")))
+    }
+}
+
+/// Aliases and workspace context needed to parse revsets the way jj-cli does.
+pub struct RevsetEnvironment<'a> {
+    jj_repo: &'a JjRepo,
+    aliases_map: RevsetAliasesMap,
+    path_converter: RepoPathUiConverter,
+    extensions: RevsetExtensions,
+}
+
+impl<'a> RevsetEnvironment<'a> {
+    pub fn new(jj_repo: &'a JjRepo) -> Self {
+        Self {
+            jj_repo,
+            aliases_map: build_aliases_map(jj_repo),
+            path_converter: jj_repo.path_converter(),
+            extensions: RevsetExtensions::default(),
+        }
+    }
+
+    pub fn parse_context(&self) -> RevsetParseContext<'_> {
+        RevsetParseContext {
+            aliases_map: &self.aliases_map,
+            local_variables: HashMap::new(),
+            user_email: self.jj_repo.user_settings().user_email(),
+            date_pattern_context: chrono::Utc::now().fixed_offset().into(),
+            default_ignored_remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
+            extensions: &self.extensions,
+            workspace: Some(RevsetWorkspaceContext {
+                path_converter: &self.path_converter,
+                workspace_name: self.jj_repo.workspace_name(),
+            }),
+        }
+    }
+
+    /// Parse a revset and resolve its symbols against `repo`.
+    pub fn resolve(
+        &self,
+        repo: &dyn Repo,
+        revset_str: &str,
+    ) -> Result<Arc<ResolvedRevsetExpression>> {
+        let mut diagnostics = RevsetDiagnostics::new();
+        let expression = parse(&mut diagnostics, revset_str, &self.parse_context())
+            .with_context(|| format!("Failed to parse revset: {}", revset_str))?;
+
+        let symbol_resolver =
+            SymbolResolver::new(repo, &([] as [&Box<dyn SymbolResolverExtension>; 0]));
+        expression
+            .resolve_user_expression(repo, &symbol_resolver)
+            .with_context(|| format!("Failed to resolve revset: {}", revset_str))
+    }
+
+    /// Like `resolve`, but keeps errors structured and also returns parser warnings.
+    pub fn resolve_with_diagnostics(
+        &self,
+        repo: &dyn Repo,
+        revset_str: &str,
+    ) -> (
+        Result<Arc<ResolvedRevsetExpression>, RevsetError>,
+        Vec<RevsetError>,
+    ) {
+        let mut diagnostics = RevsetDiagnostics::new();
+        let parsed = parse(&mut diagnostics, revset_str, &self.parse_context());
+        let warnings = diagnostics
+            .iter()
+            .map(|warning| RevsetError::from_parse_error(warning, revset_str))
+            .collect();
+
+        let expression = match parsed {
+            Ok(expression) => expression,
+            Err(err) => {
+                return (
+                    Err(RevsetError::from_parse_error(&err, revset_str)),
+                    warnings,
+                );
+            }
+        };
+
+        let symbol_resolver =
+            SymbolResolver::new(repo, &([] as [&Box<dyn SymbolResolverExtension>; 0]));
+        let resolved = expression
+            .resolve_user_expression(repo, &symbol_resolver)
+            .map_err(|err| RevsetError::from_resolution_error(&err));
+        (resolved, warnings)
+    }
+
+    /// Suggest functions, aliases, bookmarks, tags and remotes for the word being typed
+    /// at the end of `input`.
+    pub fn complete(&self, repo: &dyn Repo, input: &str) -> RevsetCompletions {
+        let start = partial_symbol_start(input);
+        let partial = &input[start..];
+
+        let mut items = Vec::new();
+        let mut push = |label: String, insert_text: String, kind: &str| {
+            if insert_text.starts_with(partial) {
+                items.push(RevsetCompletion {
+                    label,
+                    insert_text,
+                    kind: kind.to_string(),
+                });
+            }
+        };
+
+        let view = repo.view();
+        let remotes: BTreeSet<&str> = view
+            .all_remote_bookmarks()
+            .map(|(symbol, _)| symbol.remote.as_str())
+            .filter(|remote| *remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO.as_str())
+            .collect();
+
+        // `name@rem` completes the remote part only
+        if let Some((name, _)) = partial.split_once('@') {
+            for remote in &remotes {
+                let symbol = format!("{}@{}", name, remote);
+                push(symbol.clone(), symbol, "remote");
+            }
+            return RevsetCompletions {
+                start,
+                start_utf16: utf16_offset(input, start),
+                items,
+            };
+        }
+
+        for name in BUILTIN_FUNCTIONS {
+            push(format!("{}()", name), format!("{}(", name), "function");
+        }
+        for name in self.aliases_map.function_names() {
+            push(format!("{}()", name), format!("{}(", name), "alias");
+        }
+        for name in self.aliases_map.symbol_names() {
+            push(name.to_string(), name.to_string(), "alias");
+        }
+        for (name, _) in view.local_bookmarks() {
+            push(
+                name.as_str().to_string(),
+                name.as_str().to_string(),
+                "bookmark",
+            );
+        }
+        for (symbol, _) in view.all_remote_bookmarks() {
+            if remotes.contains(symbol.remote.as_str()) {
+                let text = format!("{}@{}", symbol.name.as_str(), symbol.remote.as_str());
+                push(text.clone(), text, "bookmark");
+            }
+        }
+        for (name, _) in view.local_tags() {
+            push(name.as_str().to_string(), name.as_str().to_string(), "tag");
+        }
+
+        // Builtins and user aliases can share a name; keep the first
+        let mut seen = BTreeSet::new();
+        items.retain(|item| seen.insert(item.insert_text.clone()));
+
+        RevsetCompletions {
+            start,
+            start_utf16: utf16_offset(input, start),
+            items,
+        }
+    }
+
+    /// Parse, resolve and evaluate a revset against `repo`.
+    pub fn evaluate<'r>(
+        &self,
+        repo: &'r dyn Repo,
+        revset_str: &str,
+    ) -> Result<Box<dyn Revset + 'r>> {
+        self.resolve(repo, revset_str)?
+            .evaluate(repo)
+            .with_context(|| format!("Failed to evaluate revset: {}", revset_str))
+    }
+}
+
+/// Byte offset where the symbol at the end of `input` starts, scanning back the way
+/// jj's lexer reads identifiers: runs of identifier characters joined by single `.`,
+/// `-` or `+`, optionally as `name@remote`. Anything else, such as `..`, `::`, `x-`
+/// or a bare `@`, is an operator and ends the symbol.
+fn partial_symbol_start(input: &str) -> usize {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let is_part = |i: usize| chars.get(i).is_some_and(|(_, c)| is_identifier_char(*c));
+    let mut start = chars.len();
+    let mut seen_at = false;
+    while start > 0 {
+        let c = chars[start - 1].1;
+        let joined = start >= 2 && is_part(start - 2);
+        if is_identifier_char(c)
+            || (matches!(c, '.' | '-' | '+') && joined && is_part(start))
+            || (c == '@' && joined && !seen_at)
+        {
+            seen_at |= c == '@';
+            start -= 1;
+        } else {
+            break;
+        }
+    }
+    chars.get(start).map_or(input.len(), |(i, _)| *i)
+}
+
+/// Characters of an identifier part in jj's revset grammar
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '/')
+}
+
+fn similarity_hint(candidates: &[String]) -> Option<String> {
+    if candidates.is_empty() {
+        return None;
+    }
+    let quoted: Vec<String> = candidates.iter().map(|c| format!("`{}`", c)).collect();
+    Some(format!("Did you mean {}?", quoted.join(", ")))
+}
+
+fn parse_error_hints(kind: &RevsetParseErrorKind) -> Vec<String> {
+    match kind {
+        RevsetParseErrorKind::NotPrefixOperator {
+            similar_op,
+            description,
+            ..
+        }
+        | RevsetParseErrorKind::NotPostfixOperator {
+            similar_op,
+            description,
+            ..
+        }
+        | RevsetParseErrorKind::NotInfixOperator {
+            similar_op,
+            description,
+            ..
+        } => {
+            vec![format!(
+                "Did you mean `{}` for {}?",
+                similar_op, description
+            )]
+        }
+        RevsetParseErrorKind::NoSuchFunction { candidates, .. } => {
+            similarity_hint(candidates).into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The `= ...` note at the end of a rendered pest error.
+fn rendered_note(rendered: &str) -> Option<String> {
+    rendered
+        .lines()
+        .rev()
+        .find_map(|line| line.trim_start().strip_prefix("= "))
+        .map(str::to_string)
+}
+
+/// Recover the span of a parse error from its rendering, since jj-lib keeps the
+/// underlying pest error private. Pest renders errors as
+///
+/// ```text
+///  --> 1:5
+///   |
+/// 1 | foo(bar
+///   |     ^-^
+/// ```
+///
+/// where `^-^` marks a span and `^---` a single position.
+fn rendered_span(rendered: &str, input: &str) -> Option<RevsetSpan> {
+    let mut lines = rendered.lines();
+    let (line, col) = lines
+        .find_map(|line| line.trim_start().strip_prefix("--> "))?
+        .split_once(':')?;
+    let (line, col): (usize, usize) = (line.parse().ok()?, col.parse().ok()?);
+
+    // Byte offset of the 1-based (line, char column) position
+    let line_start: usize = input
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    let line_text = input.get(line_start..)?.split('\n').next()?;
+    let char_offset = |n: usize| {
+        line_text
+            .char_indices()
+            .nth(n)
+            .map_or(line_text.len(), |(i, _)| i)
+    };
+    let start = line_start + char_offset(col.checked_sub(1)?);
+
+    let marker = lines
+        .filter_map(|line| line.split_once('|').map(|(_, rest)| rest.trim()))
+        .find(|rest| rest.starts_with('^'))?;
+    let marker_chars = marker.chars().count();
+    let end = if marker_chars > 1 && marker.ends_with('-') {
+        start
+    } else {
+        line_start + char_offset(col - 1 + marker_chars)
+    };
+
+    Some(RevsetSpan::new(input, start, end))
+}
+
+fn build_aliases_map(jj_repo: &JjRepo) -> RevsetAliasesMap {
+    let mut aliases_map = RevsetAliasesMap::new();
+
+    // trunk() - jj-cli style using remote_bookmarks with fallback to root
+    aliases_map
+        .insert(
+            "trunk()",
+            r#"latest(
+            remote_bookmarks(exact:"main", exact:"origin") |
+            remote_bookmarks(exact:"master", exact:"origin") |
+            remote_bookmarks(exact:"trunk", exact:"origin") |
+            root()
+        )"#,
+        )
+        .ok();
+
+    // builtin_immutable_heads() - trunk + tags + untracked remote bookmarks
+    aliases_map
+        .insert(
+            "builtin_immutable_heads()",
+            "present(trunk()) | tags() | untracked_remote_bookmarks()",
+        )
+        .ok();
+
+    // immutable_heads() - defaults to builtin
+    aliases_map
+        .insert("immutable_heads()", "builtin_immutable_heads()")
+        .ok();
+
+    // mine() - commits authored by current user
+    let mine_revset = format!(
+        r#"author_email(exact-i:"{}")"#,
+        jj_repo.user_settings().user_email()
+    );
+    aliases_map.insert("mine()", &mine_revset).ok();
+
+    // [revset-aliases] from user and repo config override the builtins above.
+    // Layers are ordered from lowest to highest precedence.
+    for layer in jj_repo.user_settings().config().layers() {
+        let Ok(Some(table)) = layer.look_up_table(["revset-aliases"]) else {
+            continue;
+        };
+        for (decl, item) in table.iter() {
+            if let Some(definition) = item.as_str() {
+                aliases_map.insert(decl, definition).ok();
+            }
+        }
+    }
+
+    aliases_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error_span(input: &str) -> Option<(usize, usize)> {
+        let err = jj_lib::revset::parse_program(input).unwrap_err();
+        rendered_span(&err.to_string(), input).map(|span| (span.start, span.end))
+    }
+
+    #[test]
+    fn span_of_position_error() {
+        assert_eq!(parse_error_span("a ~ ~"), Some((5, 5)));
+        // Columns count characters; offsets are into the whole multi-line input
+        assert_eq!(parse_error_span("a |\nb &"), Some((7, 7)));
+    }
+
+    #[test]
+    fn span_of_range_error() {
+        let rendered =
+            " --> 1:5\n  |\n1 | foo(bar\n  |     ^-^\n  |\n  = Function `foo` doesn't exist";
+        assert_eq!(
+            rendered_span(rendered, "foo(bar"),
+            Some(RevsetSpan::new("foo(bar", 4, 7))
+        );
+    }
+
+    #[test]
+    fn spans_in_bytes_and_utf16_units() {
+        let err = jj_lib::revset::parse_program("é:b").unwrap_err();
+        let span = rendered_span(&err.to_string(), "é:b").unwrap();
+        assert_eq!((span.start, span.end), (2, 3));
+        assert_eq!((span.start_utf16, span.end_utf16), (1, 2));
+
+        let input = "\"😀\" ~ ~";
+        let err = jj_lib::revset::parse_program(input).unwrap_err();
+        let span = rendered_span(&err.to_string(), input).unwrap();
+        assert_eq!((span.start, span.end), (10, 10));
+        assert_eq!((span.start_utf16, span.end_utf16), (8, 8));
+    }
+
+    fn partial(input: &str) -> &str {
+        &input[partial_symbol_start(input)..]
+    }
+
+    #[test]
+    fn partial_symbol_follows_identifier_grammar() {
+        assert_eq!(partial("ma"), "ma");
+        assert_eq!(partial("main..fe"), "fe");
+        assert_eq!(partial("::fe"), "fe");
+        assert_eq!(partial("@..ma"), "ma");
+        assert_eq!(partial("x--ma"), "ma");
+        assert_eq!(partial("-ma"), "ma");
+        assert_eq!(partial("x | ma"), "ma");
+        // `.`, `-` and `+` between identifier parts belong to the symbol
+        assert_eq!(partial("feature-ab"), "feature-ab");
+        assert_eq!(partial("v1.2"), "v1.2");
+        assert_eq!(partial("main-"), "");
+        assert_eq!(partial("main@ori"), "main@ori");
+        assert_eq!(partial("main@"), "main@");
+        assert_eq!(partial("a@b@c"), "b@c");
+        assert_eq!(partial("@"), "");
+        assert_eq!(partial("tëst"), "tëst");
+    }
+
+    #[test]
+    fn span_missing_from_rendering() {
+        assert_eq!(
+            rendered_span("Revset alias `x` expanded recursively", "x"),
+            None
+        );
+    }
+
+    #[test]
+    fn trunk_alias_defers_to_jj_config_when_unset() {
+        assert_eq!(TrunkSettings::default().alias_definition(), None);
+    }
+
+    #[test]
+    fn trunk_alias_uses_configured_bookmark_and_remote() {
+        let trunk = TrunkSettings {
+            bookmark: Some("develop".to_string()),
+            remote: Some("upstream".to_string()),
+        };
+        assert_eq!(
+            trunk.alias_definition().as_deref(),
+            Some(r#"latest(remote_bookmarks(exact:"develop", exact:"upstream") | root())"#)
+        );
+    }
+
+    #[test]
+    fn trunk_alias_defaults_remote_and_bookmarks() {
+        let trunk = TrunkSettings {
+            bookmark: None,
+            remote: Some("upstream".to_string()),
+        };
+        assert_eq!(
+            trunk.alias_definition().as_deref(),
+            Some(concat!(
+                r#"latest(remote_bookmarks(exact:"main", exact:"upstream") | "#,
+                r#"remote_bookmarks(exact:"master", exact:"upstream") | "#,
+                r#"remote_bookmarks(exact:"trunk", exact:"upstream") | root())"#
+            ))
+        );
+
+        let trunk = TrunkSettings {
+            bookmark: Some("develop".to_string()),
+            remote: None,
+        };
+        assert_eq!(
+            trunk.alias_definition().as_deref(),
+            Some(r#"latest(remote_bookmarks(exact:"develop", exact:"origin") | root())"#)
+        );
+    }
+}
@@ -1,10 +1,10 @@
 use anyhow::{Context, Result};
-use jj_lib::backend::TreeValue;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::object_id::ObjectId;
 use jj_lib::repo::Repo;
 use std::path::Path;
 
+use super::diff::{self, DiffOptions};
 use super::jj::JjRepo;
 
 #[derive(Clone, Debug, serde::Serialize)]
@@ -18,10 +18,25 @@ pub struct WorkingCopyStatus {
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct ChangedFile {
     pub path: String,
+    /// "added", "modified", "deleted", "renamed" or "copied"; "mode_changed" when only
+    /// the executable bit flipped and "type_changed" between file, symlink and
+    /// submodule. Symlinks and submodules are told apart by their modes.
     pub status: String,
+    /// Source of a rename or copy
+    pub source_path: Option<String>,
+    /// Content similarity to `source_path` in percent
+    pub similarity: Option<u8>,
+    /// "file", "executable", "symlink", "submodule" or "conflict" before the change,
+    /// `None` if the path didn't exist
+    pub old_mode: Option<String>,
+    /// Same as `old_mode`, after the change
+    pub new_mode: Option<String>,
+    /// Lines added and removed; both zero for binary files
+    pub insertions: usize,
+    pub deletions: usize,
 }
 
-pub fn fetch_status(repo_path: &Path) -> Result<WorkingCopyStatus> {
+pub fn fetch_status(repo_path: &Path, options: &DiffOptions) -> Result<WorkingCopyStatus> {
     let jj_repo = JjRepo::open(repo_path)?;
     let repo = jj_repo.repo_loader().load_at_head()?;
 
@@ -37,43 +52,19 @@ pub fn fetch_status(repo_path: &Path) -> Result<WorkingCopyStatus> {
     let change_id = wc_commit.change_id();
     let description = wc_commit.description().to_string();
 
-    let parent_tree = {
-        let parents = wc_commit.parents();
-        let parent = parents.into_iter().next().context("Commit has no parent")?;
-        parent?.tree()?
-    };
-
+    // Merged parents for merge commits, so the other side doesn't show up as changes
+    let parent_tree = wc_commit.parent_tree(repo.as_ref())?;
     let wc_tree = wc_commit.tree()?;
 
-    let matcher = EverythingMatcher;
-    let mut diff_iter = parent_tree.diff_stream(&wc_tree, &matcher);
-
-    let mut files = Vec::new();
-
-    pollster::block_on(async {
-        use futures::StreamExt;
-        while let Some(entry) = diff_iter.next().await {
-            let path = entry.path;
-            let path_str = path.as_internal_file_string();
-
-            let diff_values = entry.values?;
-            let before = diff_values.before.removes().next().and_then(|v| v.as_ref());
-            let after = diff_values.after.adds().next().and_then(|v| v.as_ref());
-
-            let status = match (before, after) {
-                (Some(TreeValue::File { .. }), Some(TreeValue::File { .. })) => "modified",
-                (None, Some(_)) => "added",
-                (Some(_), None) => "deleted",
-                _ => continue,
-            };
+    let copy_records = diff::parent_copy_records(&wc_commit)?;
 
-            files.push(ChangedFile {
-                path: path_str.to_string(),
-                status: status.to_string(),
-            });
-        }
-        Ok::<(), anyhow::Error>(())
-    })?;
+    let files = diff::compute_changed_files(
+        &parent_tree,
+        &wc_tree,
+        &EverythingMatcher,
+        &copy_records,
+        options,
+    )?;
 
     Ok(WorkingCopyStatus {
         change_id: format_change_id(change_id),
@@ -4,6 +4,8 @@ use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 use tokio::sync::Mutex;
 
+use crate::repo::diff::DiffOptions;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub id: String,
@@ -11,6 +13,82 @@ pub struct Project {
     pub name: String,
     pub last_opened_at: i64,
     pub revset_preset: Option<String>,
+    /// Bookmark used for `trunk()`, overriding jj config and the main/master/trunk heuristic
+    pub trunk_bookmark: Option<String>,
+    /// Remote of the trunk bookmark (defaults to `origin` when only the bookmark is set)
+    pub trunk_remote: Option<String>,
+    /// Diff settings for this project; defaults apply when unset
+    pub diff_options: Option<DiffOptions>,
+}
+
+type ProjectRow = (
+    String,
+    String,
+    String,
+    i64,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+impl Project {
+    fn from_row(
+        (
+            id,
+            path,
+            name,
+            last_opened_at,
+            revset_preset,
+            trunk_bookmark,
+            trunk_remote,
+            diff_options,
+        ): ProjectRow,
+    ) -> Self {
+        Self {
+            id,
+            path,
+            name,
+            last_opened_at,
+            revset_preset,
+            trunk_bookmark,
+            trunk_remote,
+            // Unreadable options fall back to the defaults, but say so rather than
+            // losing them silently
+            diff_options: diff_options.and_then(|value| {
+                serde_json::from_str(&value)
+                    .inspect_err(|e| eprintln!("Ignoring diff options {:?}: {}", value, e))
+                    .ok()
+            }),
+        }
+    }
+}
+
+/// User-defined named revset for a project, selectable like the builtin presets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevsetPreset {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub revset: String,
+    pub description: Option<String>,
+    /// Sort key within the project's presets
+    pub position: i64,
+}
+
+type RevsetPresetRow = (String, String, String, String, Option<String>, i64);
+
+impl RevsetPreset {
+    fn from_row((id, project_id, name, revset, description, position): RevsetPresetRow) -> Self {
+        Self {
+            id,
+            project_id,
+            name,
+            revset,
+            description,
+            position,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,7 +131,10 @@ impl Storage {
                 path TEXT NOT NULL UNIQUE,
                 name TEXT NOT NULL,
                 last_opened_at INTEGER NOT NULL,
-                revset_preset TEXT
+                revset_preset TEXT,
+                trunk_bookmark TEXT,
+                trunk_remote TEXT,
+                diff_options TEXT
             )
             "#,
         )
@@ -65,6 +146,35 @@ impl Storage {
             .execute(&pool)
             .await;
 
+        // Migration: add trunk columns if they don't exist
+        let _ = sqlx::query("ALTER TABLE projects ADD COLUMN trunk_bookmark TEXT")
+            .execute(&pool)
+            .await;
+        let _ = sqlx::query("ALTER TABLE projects ADD COLUMN trunk_remote TEXT")
+            .execute(&pool)
+            .await;
+
+        // Migration: add diff_options column (JSON) if it doesn't exist
+        let _ = sqlx::query("ALTER TABLE projects ADD COLUMN diff_options TEXT")
+            .execute(&pool)
+            .await;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS revset_presets (
+                id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                revset TEXT NOT NULL,
+                description TEXT,
+                position INTEGER NOT NULL DEFAULT 0,
+                UNIQUE(project_id, name)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS layout (
@@ -94,34 +204,34 @@ impl Storage {
     }
 
     pub async fn get_projects(&self) -> anyhow::Result<Vec<Project>> {
-        let rows: Vec<(String, String, String, i64, Option<String>)> = sqlx::query_as(
-            "SELECT id, path, name, last_opened_at, revset_preset FROM projects ORDER BY last_opened_at DESC",
+        let rows: Vec<ProjectRow> = sqlx::query_as(
+            "SELECT id, path, name, last_opened_at, revset_preset, trunk_bookmark, trunk_remote, diff_options FROM projects ORDER BY last_opened_at DESC",
         )
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows
-            .into_iter()
-            .map(|(id, path, name, last_opened_at, revset_preset)| Project {
-                id,
-                path,
-                name,
-                last_opened_at,
-                revset_preset,
-            })
-            .collect())
+        Ok(rows.into_iter().map(Project::from_row).collect())
     }
 
     pub async fn upsert_project(&self, project: &Project) -> anyhow::Result<()> {
+        let diff_options = project
+            .diff_options
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+
         sqlx::query(
             r#"
-            INSERT INTO projects (id, path, name, last_opened_at, revset_preset)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO projects (id, path, name, last_opened_at, revset_preset, trunk_bookmark, trunk_remote, diff_options)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(id) DO UPDATE SET
                 path = excluded.path,
                 name = excluded.name,
                 last_opened_at = excluded.last_opened_at,
-                revset_preset = excluded.revset_preset
+                revset_preset = excluded.revset_preset,
+                trunk_bookmark = excluded.trunk_bookmark,
+                trunk_remote = excluded.trunk_remote,
+                diff_options = excluded.diff_options
             "#,
         )
         .bind(&project.id)
@@ -129,6 +239,9 @@ impl Storage {
         .bind(&project.name)
         .bind(project.last_opened_at)
         .bind(&project.revset_preset)
+        .bind(&project.trunk_bookmark)
+        .bind(&project.trunk_remote)
+        .bind(&diff_options)
         .execute(&self.pool)
         .await?;
 
@@ -136,19 +249,13 @@ impl Storage {
     }
 
     pub async fn find_project_by_path(&self, path: &str) -> anyhow::Result<Option<Project>> {
-        let row: Option<(String, String, String, i64, Option<String>)> =
-            sqlx::query_as("SELECT id, path, name, last_opened_at, revset_preset FROM projects WHERE path = ?")
+        let row: Option<ProjectRow> =
+            sqlx::query_as("SELECT id, path, name, last_opened_at, revset_preset, trunk_bookmark, trunk_remote, diff_options FROM projects WHERE path = ?")
                 .bind(path)
                 .fetch_optional(&self.pool)
                 .await?;
 
-        Ok(row.map(|(id, path, name, last_opened_at, revset_preset)| Project {
-            id,
-            path,
-            name,
-            last_opened_at,
-            revset_preset,
-        }))
+        Ok(row.map(Project::from_row))
     }
 
     pub async fn delete_project(&self, id: &str) -> anyhow::Result<()> {
@@ -156,6 +263,10 @@ impl Storage {
             .bind(id)
             .execute(&self.pool)
             .await?;
+        sqlx::query("DELETE FROM revset_presets WHERE project_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
 
         // If the deleted project was active, clear persisted layout selection.
         let mut layout = self.layout.lock().await;
@@ -173,6 +284,51 @@ impl Storage {
         Ok(())
     }
 
+    pub async fn get_revset_presets(&self, project_id: &str) -> anyhow::Result<Vec<RevsetPreset>> {
+        let rows: Vec<RevsetPresetRow> = sqlx::query_as(
+            "SELECT id, project_id, name, revset, description, position FROM revset_presets WHERE project_id = ? ORDER BY position, name",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(RevsetPreset::from_row).collect())
+    }
+
+    pub async fn upsert_revset_preset(&self, preset: &RevsetPreset) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO revset_presets (id, project_id, name, revset, description, position)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                project_id = excluded.project_id,
+                name = excluded.name,
+                revset = excluded.revset,
+                description = excluded.description,
+                position = excluded.position
+            "#,
+        )
+        .bind(&preset.id)
+        .bind(&preset.project_id)
+        .bind(&preset.name)
+        .bind(&preset.revset)
+        .bind(&preset.description)
+        .bind(preset.position)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_revset_preset(&self, id: &str) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM revset_presets WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_layout(&self) -> AppLayout {
         self.layout.lock().await.clone()
     }
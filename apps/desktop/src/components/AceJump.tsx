@@ -127,7 +127,7 @@ export function AceJump({ revisions, repoPath, onJump }: AceJumpProps) {
 				const result = await resolveRevset(repoPath, query.trim());
 				setRevsetResult({
 					changeIds: result.change_ids,
-					error: result.error,
+					error: result.error?.message ?? null,
 					loading: false,
 					label: query,
 				});
@@ -30,6 +30,22 @@ function StatusIndicator({ status }: { status: ChangedFile["status"] }) {
 			label: "D",
 			className: "bg-red-500/20 text-red-600 dark:text-red-400 border-red-500/40",
 		},
+		renamed: {
+			label: "R",
+			className: "bg-blue-500/20 text-blue-600 dark:text-blue-400 border-blue-500/40",
+		},
+		copied: {
+			label: "C",
+			className: "bg-blue-500/20 text-blue-600 dark:text-blue-400 border-blue-500/40",
+		},
+		mode_changed: {
+			label: "X",
+			className: "bg-purple-500/20 text-purple-600 dark:text-purple-400 border-purple-500/40",
+		},
+		type_changed: {
+			label: "T",
+			className: "bg-purple-500/20 text-purple-600 dark:text-purple-400 border-purple-500/40",
+		},
 	};
 
 	const config = statusConfig[status];
@@ -46,6 +62,52 @@ function StatusIndicator({ status }: { status: ChangedFile["status"] }) {
 	);
 }
 
+/**
+ * `+12 −3` with a bar of up to five blocks, like `jj diff --stat`: the file with the most
+ * changed lines (`maxChanges`) fills the bar and the others are scaled to it.
+ */
+function DiffStatBar({
+	insertions,
+	deletions,
+	maxChanges,
+}: {
+	insertions: number;
+	deletions: number;
+	maxChanges: number;
+}) {
+	const total = insertions + deletions;
+	if (total === 0) return null;
+
+	const blocks = 5;
+	// Any change gets at least one block of its colour
+	const scale = (lines: number) =>
+		lines === 0 ? 0 : Math.max(1, Math.round((lines / Math.max(maxChanges, total)) * blocks));
+	const removed = Math.min(scale(deletions), blocks - (insertions > 0 ? 1 : 0));
+	const added = Math.min(scale(insertions), blocks - removed);
+
+	return (
+		<span className="flex items-center gap-1.5 font-mono text-[10px] shrink-0">
+			{insertions > 0 && <span className="text-green-600 dark:text-green-400">+{insertions}</span>}
+			{deletions > 0 && <span className="text-red-600 dark:text-red-400">−{deletions}</span>}
+			<span className="flex gap-px">
+				{Array.from({ length: blocks }).map((_, index) => (
+					<span
+						key={index}
+						className={cn(
+							"w-1.5 h-1.5",
+							index < added
+								? "bg-green-500"
+								: index < added + removed
+									? "bg-red-500"
+									: "bg-muted-foreground/20",
+						)}
+					/>
+				))}
+			</span>
+		</span>
+	);
+}
+
 function FileListItem({
 	file,
 	isFocused,
@@ -53,6 +115,7 @@ function FileListItem({
 	onClick,
 	onToggleSelection,
 	showSelection,
+	maxChanges,
 }: {
 	file: ChangedFile;
 	isFocused: boolean;
@@ -60,6 +123,7 @@ function FileListItem({
 	onClick: () => void;
 	onToggleSelection?: () => void;
 	showSelection?: boolean;
+	maxChanges: number;
 }) {
 	return (
 		<button
@@ -97,10 +161,19 @@ function FileListItem({
 					"font-mono text-xs truncate flex-1",
 					isFocused ? "text-foreground" : "text-muted-foreground group-hover:text-foreground",
 				)}
-				title={file.path}
+				title={
+					file.source_path
+						? `${file.source_path} → ${file.path} (${file.similarity ?? 0}% similar)`
+						: file.path
+				}
 			>
-				{file.path}
+				{file.source_path ? `${file.source_path} → ${file.path}` : file.path}
 			</span>
+			<DiffStatBar
+				insertions={file.insertions}
+				deletions={file.deletions}
+				maxChanges={maxChanges}
+			/>
 		</button>
 	);
 }
@@ -158,6 +231,7 @@ export function ChangedFilesList({
 	const filesCount = files.length;
 	const fileWord = filesCount === 1 ? "file" : "files";
 	const selectedCount = selectedFiles?.size ?? 0;
+	const maxChanges = Math.max(...files.map((file) => file.insertions + file.deletions));
 
 	return (
 		<div>
@@ -181,6 +255,7 @@ export function ChangedFilesList({
 							onToggleFileSelection ? () => onToggleFileSelection(file.path) : undefined
 						}
 						showSelection={showSelection}
+						maxChanges={maxChanges}
 					/>
 				))}
 			</div>
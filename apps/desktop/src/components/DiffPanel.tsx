@@ -27,10 +27,21 @@ interface PrerenderedDiffPanelProps {
  * Extract file path from a unified diff patch.
  */
 function extractFilePath(patch: string): string {
-	const match = patch.match(/^\+\+\+ b\/(.+)$/m);
+	// Deleted files have `+++ /dev/null`, so fall back to the old path
+	const match = patch.match(/^\+\+\+ b\/(.+)$/m) ?? patch.match(/^--- a\/(.+)$/m);
 	return match ? match[1] : "unknown";
 }
 
+/** Mode lines that come first in a file's header */
+const FILE_MODE_HEADERS = ["old mode ", "new file mode ", "deleted file mode "];
+
+function isModeLine(line: string | undefined): boolean {
+	if (line === undefined) return false;
+	return (
+		line.startsWith("new mode ") || FILE_MODE_HEADERS.some((header) => line.startsWith(header))
+	);
+}
+
 /**
  * Split a multi-file unified diff into individual file diffs.
  */
@@ -44,7 +55,13 @@ function splitMultiFileDiff(unifiedDiff: string): string[] {
 	let currentDiff: string[] = [];
 
 	for (const line of lines) {
-		if (line.startsWith("--- a/") && currentDiff.length > 0) {
+		// A file starts at its `---` header, or at the mode line just before it. A type
+		// change is two files: the deletion and then the addition.
+		const startsFile =
+			FILE_MODE_HEADERS.some((header) => line.startsWith(header)) ||
+			((line.startsWith("--- a/") || line === "--- /dev/null") &&
+				!isModeLine(currentDiff[currentDiff.length - 1]));
+		if (startsFile && currentDiff.length > 0) {
 			fileDiffs.push(currentDiff.join("\n"));
 			currentDiff = [line];
 		} else {
@@ -312,21 +312,38 @@ export function newRevision(
 	// Create optimistic revision if we have a pre-allocated change ID
 	let optimisticRevision: Revision | null = null;
 	if (preAllocatedChangeId) {
+		const now = Date.now();
 		optimisticRevision = {
 			commit_id: `pending-${preAllocatedChangeId}`, // Temporary, will be replaced
 			change_id: preAllocatedChangeId,
 			change_id_short: preAllocatedChangeId.slice(0, 8), // Approximate short ID
+			commit_id_full: `pending-${preAllocatedChangeId}`,
+			change_id_full: preAllocatedChangeId,
 			parent_ids: [parentRevision.commit_id],
 			parent_edges: [{ parent_id: parentRevision.commit_id, edge_type: "direct" as const }],
 			description: "",
 			author: parentRevision.author, // Inherit from parent
-			timestamp: new Date().toISOString(),
+			author_email: parentRevision.author_email,
+			author_timestamp: now,
+			author_tz_offset: -new Date(now).getTimezoneOffset(),
+			committer_name: parentRevision.committer_name,
+			committer_email: parentRevision.committer_email,
+			committer_timestamp: now,
+			committer_tz_offset: -new Date(now).getTimezoneOffset(),
+			timestamp: new Date(now).toISOString(),
 			is_working_copy: true,
 			is_immutable: false,
 			is_mine: true,
 			is_trunk: false,
 			is_divergent: false,
 			divergent_index: null,
+			is_empty: true,
+			has_conflict: false,
+			is_hidden: false,
+			has_description: false,
+			files_changed: 0,
+			insertions: 0,
+			deletions: 0,
 			bookmarks: [],
 		};
 
@@ -63,6 +63,9 @@ export function useAddRepository() {
 				name,
 				last_opened_at: Date.now(),
 				revset_preset: null,
+				trunk_bookmark: existingRepository?.trunk_bookmark ?? null,
+				trunk_remote: existingRepository?.trunk_remote ?? null,
+				diff_options: existingRepository?.diff_options ?? null,
 			};
 
 			yield* Effect.tryPromise({
@@ -14,8 +14,31 @@ function generateChangeId(): string {
 	return result;
 }
 
-// Calculate shortest unique prefix for each change ID
-function calculateShortIds(revisionsRaw: Omit<Revision, "change_id_short">[]): Revision[] {
+// Fields calculateShortIds derives from the rest of the mock data
+type MockRevision = Omit<
+	Revision,
+	| "change_id_short"
+	| "commit_id_full"
+	| "change_id_full"
+	| "author_email"
+	| "author_timestamp"
+	| "author_tz_offset"
+	| "committer_name"
+	| "committer_email"
+	| "committer_timestamp"
+	| "committer_tz_offset"
+	| "is_empty"
+	| "has_conflict"
+	| "is_hidden"
+	| "has_description"
+	| "files_changed"
+	| "insertions"
+	| "deletions"
+>;
+
+// Calculate shortest unique prefix for each change ID and fill in identity fields and flags.
+// Mock revisions without a description are treated as empty working copies.
+function calculateShortIds(revisionsRaw: MockRevision[]): Revision[] {
 	const changeIds = revisionsRaw.map((r) => r.change_id);
 	const result: Revision[] = [];
 
@@ -45,9 +68,27 @@ function calculateShortIds(revisionsRaw: Omit<Revision, "change_id_short">[]): R
 			changeIdShort = changeId.slice(0, prefixLen);
 		}
 
+		const time = Date.parse(revision.timestamp);
+		const isEmpty = revision.description === "";
 		result.push({
 			...revision,
 			change_id_short: changeIdShort,
+			commit_id_full: revision.commit_id,
+			change_id_full: revision.change_id,
+			author_email: revision.author,
+			author_timestamp: time,
+			author_tz_offset: 0,
+			committer_name: revision.author,
+			committer_email: revision.author,
+			committer_timestamp: time,
+			committer_tz_offset: 0,
+			is_empty: isEmpty,
+			has_conflict: false,
+			is_hidden: false,
+			has_description: !isEmpty,
+			files_changed: isEmpty ? 0 : 2,
+			insertions: isEmpty ? 0 : 12,
+			deletions: isEmpty ? 0 : 3,
 		});
 	}
 
@@ -61,6 +102,9 @@ let mockProjects: Repository[] = [
 		name: "tatami",
 		last_opened_at: Date.now(),
 		revset_preset: null,
+		trunk_bookmark: null,
+		trunk_remote: null,
+		diff_options: null,
 	},
 	{
 		id: "mock-2",
@@ -68,6 +112,9 @@ let mockProjects: Repository[] = [
 		name: "example",
 		last_opened_at: Date.now() - 86400000,
 		revset_preset: null,
+		trunk_bookmark: null,
+		trunk_remote: null,
+		diff_options: null,
 	},
 ];
 
@@ -76,7 +123,7 @@ let mockProjects: Repository[] = [
 // Change IDs are generated randomly (jj-style: 12 chars, k-z only)
 // Short IDs are calculated as minimum unique prefixes
 // Only one "main" bookmark exists on the latest main commit
-const mockRevisionsRaw: Omit<Revision, "change_id_short">[] = [
+const mockRevisionsRaw: MockRevision[] = [
 	// Root commit
 	{
 		commit_id: "root0000000000",
@@ -853,8 +900,26 @@ const mockRevisionsRaw: Omit<Revision, "change_id_short">[] = [
 let mockRevisions: Revision[] = calculateShortIds(mockRevisionsRaw);
 
 const mockChangedFiles: ChangedFile[] = [
-	{ path: "src/main.rs", status: "modified" },
-	{ path: "README.md", status: "added" },
+	{
+		path: "src/main.rs",
+		status: "modified",
+		source_path: null,
+		similarity: null,
+		old_mode: "file",
+		new_mode: "file",
+		insertions: 12,
+		deletions: 3,
+	},
+	{
+		path: "README.md",
+		status: "added",
+		source_path: null,
+		similarity: null,
+		old_mode: null,
+		new_mode: "file",
+		insertions: 24,
+		deletions: 0,
+	},
 ];
 
 type MockHandler = (args: Record<string, unknown>) => unknown;
@@ -915,6 +980,11 @@ const handlers: Record<string, MockHandler> = {
 +This is a new line
  Welcome to the project`,
 	get_revision_changes: (): ChangedFile[] => mockChangedFiles,
+	get_revision_stats: () => ({
+		files_changed: mockChangedFiles.length,
+		insertions: mockChangedFiles.reduce((sum, file) => sum + file.insertions, 0),
+		deletions: mockChangedFiles.reduce((sum, file) => sum + file.deletions, 0),
+	}),
 	watch_repository: () => undefined,
 	unwatch_repository: () => undefined,
 	generate_change_ids: (args) => {
@@ -944,7 +1014,7 @@ const handlers: Record<string, MockHandler> = {
 		// Use provided change ID or generate new one
 		const newChangeId = providedChangeId ?? generateChangeId();
 		const newCommitId = `new${Date.now().toString(16).slice(-10)}`;
-		const newRevision: Omit<Revision, "change_id_short"> = {
+		const newRevision: MockRevision = {
 			commit_id: newCommitId,
 			change_id: newChangeId,
 			parent_ids: parentCommitIds,
@@ -1011,7 +1081,7 @@ const handlers: Record<string, MockHandler> = {
 			// Create new working copy on parent
 			const newChangeId = generateChangeId();
 			const newCommitId = `wc${Date.now().toString(16).slice(-10)}`;
-			const newRevision: Omit<Revision, "change_id_short"> = {
+			const newRevision: MockRevision = {
 				commit_id: newCommitId,
 				change_id: newChangeId,
 				parent_ids: parentCommitId ? [parentCommitId] : [],
@@ -1052,19 +1122,19 @@ const handlers: Record<string, MockHandler> = {
 		// Simple mock implementation for common revsets
 		if (revset === "@") {
 			const wc = mockRevisions.find((r) => r.is_working_copy);
-			return { change_ids: wc ? [wc.change_id] : [], error: null };
+			return { change_ids: wc ? [wc.change_id] : [], error: null, warnings: [] };
 		}
 		if (revset === "@-") {
 			const wc = mockRevisions.find((r) => r.is_working_copy);
 			if (wc && wc.parent_ids.length > 0) {
 				// Find parent by commit_id
 				const parent = mockRevisions.find((r) => r.commit_id === wc.parent_ids[0]);
-				return { change_ids: parent ? [parent.change_id] : [], error: null };
+				return { change_ids: parent ? [parent.change_id] : [], error: null, warnings: [] };
 			}
-			return { change_ids: [], error: null };
+			return { change_ids: [], error: null, warnings: [] };
 		}
 		// Default: return all revisions (mock doesn't implement full revset)
-		return { change_ids: mockRevisions.map((r) => r.change_id), error: null };
+		return { change_ids: mockRevisions.map((r) => r.change_id), error: null, warnings: [] };
 	},
 
 	// Plugin commands
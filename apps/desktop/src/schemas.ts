@@ -13,10 +13,19 @@ export const Revision = Schema.Struct({
 	commit_id: Schema.String,
 	change_id: Schema.String,
 	change_id_short: Schema.String,
+	commit_id_full: Schema.String,
+	change_id_full: Schema.String,
 	parent_ids: Schema.Array(Schema.String),
 	parent_edges: Schema.Array(ParentEdge),
 	description: Schema.String,
 	author: Schema.String,
+	author_email: Schema.String,
+	author_timestamp: Schema.Number,
+	author_tz_offset: Schema.Number,
+	committer_name: Schema.String,
+	committer_email: Schema.String,
+	committer_timestamp: Schema.Number,
+	committer_tz_offset: Schema.Number,
 	timestamp: Schema.String,
 	is_working_copy: Schema.Boolean,
 	is_immutable: Schema.Boolean,
@@ -24,16 +33,44 @@ export const Revision = Schema.Struct({
 	is_trunk: Schema.Boolean,
 	is_divergent: Schema.Boolean,
 	divergent_index: Schema.NullOr(Schema.Number),
+	is_empty: Schema.Boolean,
+	has_conflict: Schema.Boolean,
+	is_hidden: Schema.Boolean,
+	has_description: Schema.Boolean,
+	files_changed: Schema.Number,
+	insertions: Schema.Number,
+	deletions: Schema.Number,
 	bookmarks: Schema.Array(Schema.String),
 });
 export type Revision = typeof Revision.Type;
 
-export const ChangedFileStatus = Schema.Literal("added", "modified", "deleted");
+export const ChangedFileStatus = Schema.Literal(
+	"added",
+	"modified",
+	"deleted",
+	"renamed",
+	"copied",
+	"mode_changed",
+	"type_changed",
+);
 export type ChangedFileStatus = typeof ChangedFileStatus.Type;
 
+export const FileMode = Schema.Literal("file", "executable", "symlink", "submodule", "conflict");
+export type FileMode = typeof FileMode.Type;
+
 export const ChangedFile = Schema.Struct({
 	path: Schema.String,
 	status: ChangedFileStatus,
+	/** Source of a rename or copy */
+	source_path: Schema.NullOr(Schema.String),
+	/** Content similarity to `source_path` in percent */
+	similarity: Schema.NullOr(Schema.Number),
+	/** Kind of entry on each side; null where the path doesn't exist */
+	old_mode: Schema.NullOr(FileMode),
+	new_mode: Schema.NullOr(FileMode),
+	/** Lines added and removed; both zero for binary files */
+	insertions: Schema.Number,
+	deletions: Schema.Number,
 });
 export type ChangedFile = typeof ChangedFile.Type;
 
@@ -44,11 +81,34 @@ export const WorkingCopyStatus = Schema.Struct({
 });
 export type WorkingCopyStatus = typeof WorkingCopyStatus.Type;
 
+export const DiffOptions = Schema.Struct({
+	/** Unchanged lines shown around each change */
+	context: Schema.Number,
+	whitespace: Schema.Literal("exact", "ignore_amount", "ignore_all"),
+	/** Drop hunks whose changed lines are all blank */
+	ignore_blank_lines: Schema.Boolean,
+	algorithm: Schema.Literal("myers", "patience", "histogram", "jj"),
+});
+export type DiffOptions = typeof DiffOptions.Type;
+
 export const Repository = Schema.Struct({
 	id: Schema.String,
 	path: Schema.String,
 	name: Schema.String,
 	last_opened_at: Schema.Number,
 	revset_preset: Schema.NullOr(Schema.String),
+	trunk_bookmark: Schema.NullOr(Schema.String),
+	trunk_remote: Schema.NullOr(Schema.String),
+	diff_options: Schema.NullOr(DiffOptions),
 });
 export type Repository = typeof Repository.Type;
+
+export const RevsetPreset = Schema.Struct({
+	id: Schema.String,
+	project_id: Schema.String,
+	name: Schema.String,
+	revset: Schema.String,
+	description: Schema.NullOr(Schema.String),
+	position: Schema.Number,
+});
+export type RevsetPreset = typeof RevsetPreset.Type;
@@ -2,24 +2,60 @@ import { invoke } from "@tauri-apps/api/core";
 
 export type {
 	ChangedFile,
+	DiffOptions,
 	Repository,
+	RevsetPreset,
 	Revision,
 	WorkingCopyStatus,
 } from "./schemas";
 
-import type { ChangedFile, Repository, Revision, WorkingCopyStatus } from "./schemas";
+import type {
+	ChangedFile,
+	DiffOptions,
+	Repository,
+	RevsetPreset,
+	Revision,
+	WorkingCopyStatus,
+} from "./schemas";
 
 export async function findRepository(startPath: string): Promise<string | null> {
 	return invoke<string | null>("find_repository", { startPath });
 }
 
+/** `paths` restricts the log to revisions touching those files, directories or filesets */
 export async function getRevisions(
 	repoPath: string,
 	limit: number,
 	revset?: string,
 	preset?: string,
+	paths?: string[],
 ): Promise<Revision[]> {
-	return invoke<Revision[]>("get_revisions", { repoPath, limit, revset, preset });
+	return invoke<Revision[]>("get_revisions", { repoPath, limit, revset, preset, paths });
+}
+
+export interface LogPage {
+	revisions: Revision[];
+	/** Opaque token for the next page, null once the log is exhausted */
+	next_cursor: string | null;
+}
+
+/** Fetch one page of the log; pass the previous page's `next_cursor` to continue */
+export async function getRevisionsPage(
+	repoPath: string,
+	limit: number,
+	cursor?: string | null,
+	revset?: string,
+	preset?: string,
+	paths?: string[],
+): Promise<LogPage> {
+	return invoke<LogPage>("get_revisions_page", {
+		repoPath,
+		limit,
+		cursor,
+		revset,
+		preset,
+		paths,
+	});
 }
 
 export async function getStatus(repoPath: string): Promise<WorkingCopyStatus> {
@@ -30,19 +66,178 @@ export async function getFileDiff(
 	repoPath: string,
 	changeId: string,
 	filePath: string,
+	options?: DiffOptions,
+): Promise<string> {
+	return invoke<string>("get_file_diff", { repoPath, changeId, filePath, options });
+}
+
+/** Raw content of a file before (merged parents) and after a revision, e.g. to compare images */
+export async function getFileBytes(
+	repoPath: string,
+	changeId: string,
+	filePath: string,
+): Promise<{ old: ArrayBuffer; new: ArrayBuffer }> {
+	const [oldBytes, newBytes] = await Promise.all([
+		invoke<ArrayBuffer>("get_file_bytes", { repoPath, changeId, filePath, side: "old" }),
+		invoke<ArrayBuffer>("get_file_bytes", { repoPath, changeId, filePath, side: "new" }),
+	]);
+	return { old: oldBytes, new: newBytes };
+}
+
+/**
+ * `fileset` scopes the diff, e.g. `glob:"src/**"` or `~Cargo.lock`.
+ * `options` overrides the project's stored diff options.
+ * Moved blocks aren't marked here; use `getRevisionHunks` for those.
+ */
+export async function getRevisionDiff(
+	repoPath: string,
+	changeId: string,
+	fileset?: string,
+	options?: DiffOptions,
+): Promise<string> {
+	return invoke<string>("get_revision_diff", { repoPath, changeId, fileset, options });
+}
+
+export interface DiffLine {
+	kind: "context" | "added" | "removed";
+	/** Line text without its trailing newline */
+	content: string;
+	old_line: number | null;
+	new_line: number | null;
+	/** Last line of the file, with no trailing newline */
+	missing_newline: boolean;
+	/** Changed ranges of `content` (UTF-16 string indices) against the paired line */
+	inline_ranges: InlineRange[];
+	/** Set when the line belongs to a block moved elsewhere in the diff */
+	moved: LineMove | null;
+}
+
+/** Other side of a moved line: where a removed line went, or where an added line came from */
+export interface LineMove {
+	/** Shared by both sides of one moved block */
+	id: number;
+	path: string;
+	line: number;
+}
+
+export interface InlineRange {
+	start: number;
+	end: number;
+}
+
+export type InlineGranularity = "word" | "char" | "none";
+
+export interface DiffHunk {
+	old_start: number;
+	old_lines: number;
+	new_start: number;
+	new_lines: number;
+	lines: DiffLine[];
+}
+
+export interface FileDiff {
+	path: string;
+	status: ChangedFile["status"];
+	/** Source of a rename or copy */
+	source_path: string | null;
+	/** Content similarity to `source_path` in percent */
+	similarity: number | null;
+	old_mode: ChangedFile["old_mode"];
+	new_mode: ChangedFile["new_mode"];
+	hunks: DiffHunk[];
+	/** Set instead of `hunks` when either side is binary */
+	binary: BinaryDiff | null;
+}
+
+/** Sizes and hashes of a binary file; null where that side isn't a file */
+export interface BinaryDiff {
+	old_size: number | null;
+	new_size: number | null;
+	old_hash: string | null;
+	new_hash: string | null;
+}
+
+/** Structured diff of a revision against its (merged) parents */
+export async function getRevisionHunks(
+	repoPath: string,
+	changeId: string,
+	fileset?: string,
+	granularity?: InlineGranularity,
+	options?: DiffOptions,
+): Promise<FileDiff[]> {
+	return invoke<FileDiff[]>("get_revision_hunks", {
+		repoPath,
+		changeId,
+		fileset,
+		granularity,
+		options,
+	});
+}
+
+export interface ParentDiff {
+	parent_commit_id: string;
+	parent_change_id: string;
+	diff: string;
+}
+
+/** Diff against each parent separately (for merges, what each side brought in) */
+export async function getRevisionParentDiffs(
+	repoPath: string,
+	changeId: string,
+	fileset?: string,
+): Promise<ParentDiff[]> {
+	return invoke<ParentDiff[]>("get_revision_parent_diffs", { repoPath, changeId, fileset });
+}
+
+/** Diff two versions of the same change, ignoring changes brought in by rebasing */
+export async function getInterdiff(
+	repoPath: string,
+	fromCommitId: string,
+	toCommitId: string,
 ): Promise<string> {
-	return invoke<string>("get_file_diff", { repoPath, changeId, filePath });
+	return invoke<string>("get_interdiff", { repoPath, fromCommitId, toCommitId });
 }
 
-export async function getRevisionDiff(repoPath: string, changeId: string): Promise<string> {
-	return invoke<string>("get_revision_diff", { repoPath, changeId });
+/** Diff between two revsets that each name a single revision, like `jj diff --from --to` */
+export async function getRangeDiff(
+	repoPath: string,
+	from: string,
+	to: string,
+	fileset?: string,
+): Promise<string> {
+	return invoke<string>("get_range_diff", { repoPath, from, to, fileset });
+}
+
+export async function getRangeChanges(
+	repoPath: string,
+	from: string,
+	to: string,
+	fileset?: string,
+): Promise<ChangedFile[]> {
+	return invoke<ChangedFile[]>("get_range_changes", { repoPath, from, to, fileset });
 }
 
 export async function getRevisionChanges(
 	repoPath: string,
 	changeId: string,
+	fileset?: string,
 ): Promise<ChangedFile[]> {
-	return invoke<ChangedFile[]>("get_revision_changes", { repoPath, changeId });
+	return invoke<ChangedFile[]>("get_revision_changes", { repoPath, changeId, fileset });
+}
+
+/** Totals over a revision's changed files, like the summary line of `jj diff --stat` */
+export interface DiffStats {
+	files_changed: number;
+	insertions: number;
+	deletions: number;
+}
+
+export async function getRevisionStats(
+	repoPath: string,
+	changeId: string,
+	fileset?: string,
+): Promise<DiffStats> {
+	return invoke<DiffStats>("get_revision_stats", { repoPath, changeId, fileset });
 }
 
 export async function getRepositories(): Promise<Repository[]> {
@@ -61,6 +256,18 @@ export async function removeRepository(repositoryId: string): Promise<void> {
 	return invoke("remove_project", { projectId: repositoryId });
 }
 
+export async function getRevsetPresets(repositoryId: string): Promise<RevsetPreset[]> {
+	return invoke<RevsetPreset[]>("get_revset_presets", { projectId: repositoryId });
+}
+
+export async function upsertRevsetPreset(preset: RevsetPreset): Promise<void> {
+	return invoke("upsert_revset_preset", { preset });
+}
+
+export async function removeRevsetPreset(presetId: string): Promise<void> {
+	return invoke("remove_revset_preset", { presetId });
+}
+
 export async function watchRepository(repoPath: string): Promise<void> {
 	return invoke("watch_repository", { repoPath });
 }
@@ -81,12 +288,85 @@ export async function jjNew(
 	return invoke<string>("jj_new", { repoPath, parentChangeIds, changeId: changeId ?? null });
 }
 
-export async function jjEdit(repoPath: string, changeId: string): Promise<void> {
-	return invoke("jj_edit", { repoPath, changeId });
+/** Edit a revision. Immutable revisions are refused unless `ignoreImmutable` is set. */
+export async function jjEdit(
+	repoPath: string,
+	changeId: string,
+	ignoreImmutable = false,
+): Promise<void> {
+	return invoke("jj_edit", { repoPath, changeId, ignoreImmutable });
 }
 
-export async function jjAbandon(repoPath: string, changeId: string): Promise<void> {
-	return invoke("jj_abandon", { repoPath, changeId });
+/** Abandon a revision. Immutable revisions are refused unless `ignoreImmutable` is set. */
+export async function jjAbandon(
+	repoPath: string,
+	changeId: string,
+	ignoreImmutable = false,
+): Promise<void> {
+	return invoke("jj_abandon", { repoPath, changeId, ignoreImmutable });
+}
+
+/** One visible copy of a divergent change */
+export interface DivergentCopy {
+	commit_id: string;
+	description: string;
+	author: string;
+	timestamp: number;
+	parent_ids: string[];
+	is_working_copy: boolean;
+	/** Interdiff from the first copy (empty for the first copy) */
+	diff: string;
+}
+
+/** List the copies of a divergent change and how each differs from the first */
+export async function getDivergentCopies(
+	repoPath: string,
+	changeId: string,
+): Promise<DivergentCopy[]> {
+	return invoke<DivergentCopy[]>("get_divergent_copies", { repoPath, changeId });
+}
+
+/** Resolve divergence by abandoning copies and/or giving one copy a fresh change ID */
+export async function jjResolveDivergence(
+	repoPath: string,
+	changeId: string,
+	abandonCommitIds: string[],
+	renewCommitId?: string,
+	ignoreImmutable = false,
+): Promise<void> {
+	return invoke("jj_resolve_divergence", {
+		repoPath,
+		changeId,
+		abandonCommitIds,
+		renewCommitId: renewCommitId ?? null,
+		ignoreImmutable,
+	});
+}
+
+/** Outcome of a next/prev move: the new `@`, or candidates when the target was ambiguous */
+export interface MovementResult {
+	change_id: string | null;
+	candidates: string[];
+}
+
+/** Move `@` to a child revision (`jj next`) */
+export async function jjNext(
+	repoPath: string,
+	offset = 1,
+	edit = false,
+	ignoreImmutable = false,
+): Promise<MovementResult> {
+	return invoke<MovementResult>("jj_next", { repoPath, offset, edit, ignoreImmutable });
+}
+
+/** Move `@` to a parent revision (`jj prev`) */
+export async function jjPrev(
+	repoPath: string,
+	offset = 1,
+	edit = false,
+	ignoreImmutable = false,
+): Promise<MovementResult> {
+	return invoke<MovementResult>("jj_prev", { repoPath, offset, edit, ignoreImmutable });
 }
 
 /** Get recency data for commits - returns commit_id (hex) -> timestamp_millis when last WC */
@@ -97,13 +377,69 @@ export async function getCommitRecency(
 	return invoke<Record<string, number>>("get_commit_recency", { repoPath, limit });
 }
 
+/** Operation that created or rewrote a version of a change */
+export interface EvologOperation {
+	id: string;
+	description: string;
+	timestamp: number;
+}
+
+/** One version of a change in its evolution log */
+export interface EvologEntry {
+	commit_id: string;
+	change_id: string;
+	description: string;
+	author: string;
+	timestamp: number;
+	predecessor_ids: string[];
+	operation: EvologOperation | null;
+	diff: string;
+}
+
+/** List every version of a change, newest first, with a diff from the previous version */
+export async function getEvolog(
+	repoPath: string,
+	changeId: string,
+	limit: number,
+): Promise<EvologEntry[]> {
+	return invoke<EvologEntry[]>("get_evolog", { repoPath, changeId, limit });
+}
+
+export interface RevsetError {
+	kind: "parse" | "resolve" | "evaluate";
+	message: string;
+	/** Byte range into the revset text, and the same range as string indices, when known */
+	span: { start: number; end: number; start_utf16: number; end_utf16: number } | null;
+	hints: string[];
+}
+
 /** Result of resolving a revset expression */
 export interface RevsetResult {
 	change_ids: string[];
-	error: string | null;
+	error: RevsetError | null;
+	warnings: RevsetError[];
+}
+
+export interface RevsetCompletion {
+	label: string;
+	insert_text: string;
+	kind: "function" | "alias" | "bookmark" | "tag" | "remote";
+}
+
+export interface RevsetCompletions {
+	/** Byte offset where the partial word starts */
+	start: number;
+	/** The same offset as a string index */
+	start_utf16: number;
+	items: RevsetCompletion[];
 }
 
 /** Resolve a revset expression using jj-lib's full parser */
 export async function resolveRevset(repoPath: string, revset: string): Promise<RevsetResult> {
 	return invoke<RevsetResult>("resolve_revset", { repoPath, revset });
 }
+
+/** Suggest completions for the word being typed at the end of `input` */
+export async function completeRevset(repoPath: string, input: string): Promise<RevsetCompletions> {
+	return invoke<RevsetCompletions>("complete_revset", { repoPath, input });
+}